@@ -0,0 +1,40 @@
+//! Runs a `TfListener` and answers `tf2_msgs/LookupTransform` action goals on
+//! `tf2_buffer_server`, like `buffer_server` of tf2_ros, so that other nodes can look up
+//! transforms through a `BufferClient` instead of subscribing to `/tf`. The frame tree is also
+//! served on `~/tf2_frames`.
+//!
+//! Usage: `buffer_server [cache seconds]`, which defaults to 120 seconds like tf2_ros.
+
+use std::{error::Error, time::Duration};
+
+use r2r::builtin_interfaces::msg::Duration as RosDuration;
+use tf_r2r::{BufferServer, TfBuffer, TfListener};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let seconds: f64 = std::env::args()
+        .nth(1)
+        .map_or(Ok(120.0), |arg| arg.parse())?;
+
+    let ctx = r2r::Context::create()?;
+    let mut node = r2r::Node::create(ctx, "tf2_buffer_server", "")?;
+    let cache_duration = Duration::from_secs_f64(seconds);
+    let buffer = TfBuffer::new_with_duration(RosDuration {
+        sec: cache_duration.as_secs() as i32,
+        nanosec: cache_duration.subsec_nanos(),
+    });
+    let listener = TfListener::builder()
+        .buffer(buffer)
+        .frame_graph_service("~/tf2_frames")
+        .build(&mut node)?;
+    let _server = BufferServer::new(&mut node, listener, "tf2_buffer_server")?;
+    r2r::log_info!(
+        "tf2_buffer_server",
+        "Answering LookupTransform goals with a cache of {seconds} seconds"
+    );
+    tokio::task::spawn_blocking(move || loop {
+        node.spin_once(Duration::from_millis(100));
+    })
+    .await?;
+    Ok(())
+}
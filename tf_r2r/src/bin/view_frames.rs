@@ -0,0 +1,36 @@
+//! Listens to the transforms for a few seconds, and writes the frame tree as Graphviz DOT and
+//! Mermaid files, like `view_frames` of tf2.
+//!
+//! Usage: `view_frames [seconds] [file name without extension]`, which default to 5 seconds and
+//! `frames`, writing `frames.gv` and `frames.mmd`.
+
+use std::{
+    error::Error,
+    fs,
+    time::{Duration, Instant},
+};
+
+use tf_r2r::TfListener;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    let seconds: f64 = args.next().map_or(Ok(5.0), |arg| arg.parse())?;
+    let name = args.next().unwrap_or_else(|| "frames".to_string());
+
+    let ctx = r2r::Context::create()?;
+    let mut node = r2r::Node::create(ctx, "view_frames", "")?;
+    let listener = TfListener::builder().build_polling(&mut node)?;
+    r2r::log_info!(
+        "view_frames",
+        "Listening to the transforms for {seconds} seconds"
+    );
+    let deadline = Instant::now() + Duration::from_secs_f64(seconds);
+    while Instant::now() < deadline {
+        listener.spin_once(&mut node, Duration::from_millis(100));
+    }
+    let buffer = listener.buffer();
+    fs::write(format!("{name}.gv"), buffer.frames_as_dot())?;
+    fs::write(format!("{name}.mmd"), buffer.frames_as_mermaid())?;
+    r2r::log_info!("view_frames", "Wrote {name}.gv and {name}.mmd");
+    Ok(())
+}
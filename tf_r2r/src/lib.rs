@@ -1,15 +1,49 @@
 #![cfg(feature = "ros2")]
 
+pub mod qos;
+#[cfg(test)]
+mod test_utils;
 mod tf_broadcaster;
 mod tf_buffer;
+mod tf_buffer_client;
+mod tf_buffer_server;
+mod tf_cache_limits;
+mod tf_clock;
+mod tf_conflict;
 mod tf_error;
+mod tf_frame_graph;
+mod tf_frame_graph_client;
+mod tf_frame_normalization;
 mod tf_graph_node;
 mod tf_individual_transform_chain;
 mod tf_listener;
+mod tf_parent_history;
+mod tf_periodic_broadcaster;
+mod tf_sample_policy;
+mod tf_shared_buffer;
+mod tf_threshold_broadcaster;
+mod tf_transform_samples;
+mod tf_validation;
 pub mod transforms;
 mod utils;
 
-pub use tf_broadcaster::TfBroadcaster;
+pub use tf_broadcaster::{TfBroadcaster, TfBroadcasterBuilder};
 pub use tf_buffer::TfBuffer;
+pub use tf_buffer_client::BufferClient;
+pub use tf_buffer_server::BufferServer;
+pub use tf_cache_limits::CacheLimits;
+pub use tf_clock::TfClock;
+pub use tf_conflict::{ConflictTolerances, TfConflict};
 pub use tf_error::TfError;
-pub use tf_listener::TfListener;
+pub use tf_frame_graph::FrameDescription;
+pub use tf_frame_graph_client::FrameGraphClient;
+pub use tf_frame_normalization::{FrameNormalization, TfPrefix};
+pub use tf_listener::{TfListener, TfListenerBuilder, TopicStatistics};
+pub use tf_parent_history::ParentInterval;
+pub use tf_periodic_broadcaster::{PeriodicTfBroadcaster, PeriodicTfBroadcasterHandle};
+pub use tf_sample_policy::{DuplicatePolicy, LatePolicy, SamplePolicy, SampleStatistics};
+pub use tf_shared_buffer::SharedTfBuffer;
+pub use tf_threshold_broadcaster::{
+    BroadcastStatistics, BroadcastThreshold, ThresholdTfBroadcaster,
+};
+pub use tf_validation::ValidationPolicy;
@@ -0,0 +1,61 @@
+//! Quality of service profiles used by tf2_ros
+//!
+//! These match the `DynamicListenerQoS`, `StaticListenerQoS`, `DynamicBroadcasterQoS` and
+//! `StaticBroadcasterQoS` of tf2_ros, so that the listeners and broadcasters of this crate
+//! interoperate with the standard ROS2 tools.
+
+use r2r::QosProfile;
+
+/// QoS of `/tf` subscribers: reliable, volatile, keep last 100
+pub fn dynamic_listener_qos() -> QosProfile {
+    QosProfile::default().keep_last(100)
+}
+
+/// QoS of `/tf_static` subscribers: reliable, transient local, keep all
+///
+/// Transient local durability is needed to receive the transforms which were latched by static
+/// publishers before the listener was created.
+pub fn static_listener_qos() -> QosProfile {
+    QosProfile::default()
+        .keep_all()
+        .reliable()
+        .transient_local()
+}
+
+/// QoS of `/tf` publishers: reliable, volatile, keep last 100
+pub fn dynamic_broadcaster_qos() -> QosProfile {
+    QosProfile::default().keep_last(100)
+}
+
+/// QoS of `/tf_static` publishers: reliable, transient local, keep last 1
+pub fn static_broadcaster_qos() -> QosProfile {
+    QosProfile::default()
+        .keep_last(1)
+        .reliable()
+        .transient_local()
+}
+
+#[cfg(test)]
+mod test {
+    use r2r::qos::{DurabilityPolicy, HistoryPolicy, ReliabilityPolicy};
+
+    use super::*;
+
+    #[test]
+    fn test_static_qos_is_latched() {
+        for qos in [static_listener_qos(), static_broadcaster_qos()] {
+            assert_eq!(qos.durability, DurabilityPolicy::TransientLocal);
+            assert_eq!(qos.reliability, ReliabilityPolicy::Reliable);
+        }
+        assert_eq!(static_listener_qos().history, HistoryPolicy::KeepAll);
+    }
+
+    #[test]
+    fn test_dynamic_qos() {
+        for qos in [dynamic_listener_qos(), dynamic_broadcaster_qos()] {
+            assert_eq!(qos.durability, DurabilityPolicy::Volatile);
+            assert_eq!(qos.history, HistoryPolicy::KeepLast);
+            assert_eq!(qos.depth, 100);
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Helpers shared by the unit tests
+
+use r2r::{builtin_interfaces::msg::Time, geometry_msgs::msg::TransformStamped};
+
+/// Returns the transform from `parent` to `child` at `sec`, translated by `x` along the X axis
+pub(crate) fn transform(parent: &str, child: &str, sec: i32, x: f64) -> TransformStamped {
+    transform_with_yaw(parent, child, sec, x, 0.0)
+}
+
+/// Returns the transform from `parent` to `child` at `sec`, translated by `x` along the X axis and
+/// rotated by `yaw` around the Z axis
+pub(crate) fn transform_with_yaw(
+    parent: &str,
+    child: &str,
+    sec: i32,
+    x: f64,
+    yaw: f64,
+) -> TransformStamped {
+    let mut tf = TransformStamped::default();
+    tf.header.frame_id = parent.to_string();
+    tf.header.stamp = Time { sec, nanosec: 0 };
+    tf.child_frame_id = child.to_string();
+    tf.transform.translation.x = x;
+    tf.transform.rotation.z = (yaw / 2.0).sin();
+    tf.transform.rotation.w = (yaw / 2.0).cos();
+    tf
+}
@@ -1,6 +1,6 @@
 use r2r::{geometry_msgs::msg::TransformStamped, tf2_msgs::msg::TFMessage, QosProfile};
 
-use crate::tf_error::TfError;
+use crate::{qos::dynamic_broadcaster_qos, tf_error::TfError, tf_listener::namespaced_topics};
 
 pub struct TfBroadcaster {
     publisher: r2r::Publisher<TFMessage>,
@@ -8,21 +8,92 @@ pub struct TfBroadcaster {
 
 impl TfBroadcaster {
     /// Create a new TfBroadcaster
+    ///
+    /// # Panics
+    ///
+    /// Panics if the publisher could not be created. Use [`TfBroadcaster::try_new`] to handle
+    /// the error instead.
     #[track_caller]
     pub fn new(node: &mut r2r::Node) -> Self {
-        Self {
-            publisher: node.create_publisher("/tf", QosProfile::default()).unwrap(),
-        }
+        Self::try_new(node).unwrap()
+    }
+
+    /// Create a new TfBroadcaster, returning an error if the publisher could not be created.
+    pub fn try_new(node: &mut r2r::Node) -> Result<Self, TfError> {
+        Self::builder().build(node)
+    }
+
+    /// Returns a builder to configure the topic and queue size of a TfBroadcaster
+    pub fn builder() -> TfBroadcasterBuilder {
+        TfBroadcasterBuilder::default()
     }
 
     /// Broadcast transform
     pub fn send_transform(&self, tf: TransformStamped) -> Result<(), TfError> {
-        let tf_message = TFMessage {
-            transforms: vec![tf],
-        };
-        // TODO: handle error correctly
+        self.send_transforms(vec![tf])
+    }
+
+    /// Broadcast multiple transforms in a single message
+    pub fn send_transforms(&self, transforms: Vec<TransformStamped>) -> Result<(), TfError> {
+        if transforms.is_empty() {
+            return Ok(());
+        }
         self.publisher
-            .publish(&tf_message)
+            .publish(&TFMessage { transforms })
             .map_err(|err| TfError::R2r(err.to_string()))
     }
 }
+
+/// Builder of [`TfBroadcaster`]
+#[derive(Debug)]
+#[must_use]
+pub struct TfBroadcasterBuilder {
+    topic: String,
+    qos: QosProfile,
+}
+
+impl Default for TfBroadcasterBuilder {
+    fn default() -> Self {
+        Self {
+            topic: "/tf".to_string(),
+            qos: dynamic_broadcaster_qos(),
+        }
+    }
+}
+
+impl TfBroadcasterBuilder {
+    /// Sets the topic to publish to. The default is `/tf`.
+    pub fn topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = topic.into();
+        self
+    }
+
+    /// Publishes to `tf` in the given namespace
+    pub fn namespace(self, namespace: &str) -> Self {
+        self.topic(namespaced_topics(namespace).0)
+    }
+
+    /// Sets the history depth of the publisher. The default is 100.
+    pub fn queue_size(mut self, queue_size: usize) -> Self {
+        self.qos = self.qos.keep_last(queue_size);
+        self
+    }
+
+    /// Sets the QoS of the publisher. The default is [`dynamic_broadcaster_qos`].
+    ///
+    /// Use [`static_broadcaster_qos`](crate::qos::static_broadcaster_qos) together with the
+    /// `/tf_static` topic to latch static transforms.
+    pub fn qos(mut self, qos: QosProfile) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    /// Creates the TfBroadcaster
+    pub fn build(self, node: &mut r2r::Node) -> Result<TfBroadcaster, TfError> {
+        Ok(TfBroadcaster {
+            publisher: node
+                .create_publisher(&self.topic, self.qos)
+                .map_err(|err| TfError::R2r(err.to_string()))?,
+        })
+    }
+}
@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use r2r::{
+    builtin_interfaces::msg::{Duration as RosDuration, Time},
+    geometry_msgs::msg::TransformStamped,
+    tf2_msgs::{
+        action::LookupTransform::{Action, Goal, Result as LookupResult},
+        msg::TF2Error,
+    },
+    GoalStatus,
+};
+
+use crate::{tf_error::TfError, utils::duration_as_ns_i64};
+
+const DEFAULT_TIMEOUT_PADDING: Duration = Duration::from_secs(2);
+
+/// Looks up transforms through a [`BufferServer`](crate::BufferServer) or a `BufferServer` of
+/// tf2_ros, like `BufferClient` of tf2_ros
+///
+/// This has the same lookup API as [`TfListener`](crate::TfListener), but does not subscribe to
+/// `/tf`, which keeps the traffic and memory of the node low. Each lookup sends a
+/// `tf2_msgs/LookupTransform` goal and waits for its result, so the lookups are async and the
+/// node must be spun meanwhile:
+///
+/// ```no_run
+/// #[tokio::main]
+/// async fn main() {
+///     let ctx = r2r::Context::create().unwrap();
+///     let mut node = r2r::Node::create(ctx, "client", "").unwrap();
+///     let client = tf_r2r::BufferClient::new(&mut node, "/tf2_buffer_server").unwrap();
+///     tokio::task::spawn_blocking(move || loop {
+///         node.spin_once(std::time::Duration::from_millis(100));
+///     });
+///     client.wait_for_server().await.unwrap();
+///     let tf = client
+///         .lookup_transform("camera", "base_link", Default::default())
+///         .await;
+///     println!("{tf:?}");
+/// }
+/// ```
+///
+/// Errors of the server are returned as [`TfError::BufferServer`].
+pub struct BufferClient {
+    client: r2r::ActionClient<Action>,
+    timeout_padding: Duration,
+}
+
+impl BufferClient {
+    /// Creates a client of the action with the given name
+    pub fn new(node: &mut r2r::Node, action_name: &str) -> Result<Self, TfError> {
+        let client = node
+            .create_action_client::<Action>(action_name)
+            .map_err(|err| TfError::R2r(err.to_string()))?;
+        Ok(Self {
+            client,
+            timeout_padding: DEFAULT_TIMEOUT_PADDING,
+        })
+    }
+
+    /// Sets how long the client waits for a result in addition to the timeout of the lookup. The
+    /// default is 2 seconds, like in tf2_ros.
+    pub fn with_timeout_padding(mut self, timeout_padding: Duration) -> Self {
+        self.timeout_padding = timeout_padding;
+        self
+    }
+
+    /// Waits until the server is available
+    pub async fn wait_for_server(&self) -> Result<(), TfError> {
+        r2r::Node::is_available(&self.client)
+            .map_err(|err| TfError::R2r(err.to_string()))?
+            .await
+            .map_err(|err| TfError::R2r(err.to_string()))
+    }
+
+    /// Looks up a transform within the tree at a given time.
+    pub async fn lookup_transform(
+        &self,
+        from: &str,
+        to: &str,
+        time: Time,
+    ) -> Result<TransformStamped, TfError> {
+        self.wait_for_transform(from, to, time, Duration::ZERO)
+            .await
+    }
+
+    /// Waits until the transform is available or the timeout elapses.
+    pub async fn wait_for_transform(
+        &self,
+        from: &str,
+        to: &str,
+        time: Time,
+        timeout: Duration,
+    ) -> Result<TransformStamped, TfError> {
+        self.send_goal(Goal {
+            target_frame: from.to_string(),
+            source_frame: to.to_string(),
+            source_time: time,
+            timeout: goal_timeout(timeout),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Looks up a transform within the tree at a given time.
+    pub async fn lookup_transform_with_time_travel(
+        &self,
+        from: &str,
+        time1: Time,
+        to: &str,
+        time2: Time,
+        fixed_frame: &str,
+    ) -> Result<TransformStamped, TfError> {
+        self.send_goal(Goal {
+            target_frame: from.to_string(),
+            target_time: time1,
+            source_frame: to.to_string(),
+            source_time: time2,
+            fixed_frame: fixed_frame.to_string(),
+            advanced: true,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Sends the goal and waits for its result, or cancels it if the timeout of the goal and the
+    /// padding elapse first
+    async fn send_goal(&self, goal: Goal) -> Result<TransformStamped, TfError> {
+        let timeout = Duration::from_nanos(duration_as_ns_i64(&goal.timeout).max(0) as u64)
+            .saturating_add(self.timeout_padding);
+        let deadline = tokio::time::Instant::now() + timeout;
+        let request = self
+            .client
+            .send_goal_request(goal)
+            .map_err(|err| TfError::R2r(err.to_string()))?;
+        let (handle, result, _feedback) = tokio::time::timeout_at(deadline, request)
+            .await
+            .map_err(|_| timeout_error())?
+            .map_err(|err| TfError::R2r(err.to_string()))?;
+        match tokio::time::timeout_at(deadline, result).await {
+            Ok(result) => {
+                let (status, result) = result.map_err(|err| TfError::R2r(err.to_string()))?;
+                transform_from_result(status, result)
+            }
+            Err(_) => {
+                // The cancel request is sent right away, so its answer is not awaited.
+                let _ = handle.cancel();
+                Err(timeout_error())
+            }
+        }
+    }
+}
+
+/// Converts the timeout of a lookup to the timeout of its goal, saturating at the longest timeout
+/// a goal can hold
+fn goal_timeout(timeout: Duration) -> RosDuration {
+    match i32::try_from(timeout.as_secs()) {
+        Ok(sec) => RosDuration {
+            sec,
+            nanosec: timeout.subsec_nanos(),
+        },
+        Err(_) => RosDuration {
+            sec: i32::MAX,
+            nanosec: 999_999_999,
+        },
+    }
+}
+
+fn timeout_error() -> TfError {
+    TfError::BufferServer(
+        TF2Error::TIMEOUT_ERROR as u8,
+        "the buffer server did not answer in time".to_string(),
+    )
+}
+
+fn transform_from_result(
+    status: GoalStatus,
+    result: LookupResult,
+) -> Result<TransformStamped, TfError> {
+    if status != GoalStatus::Succeeded {
+        return Err(TfError::BufferServer(
+            TF2Error::TRANSFORM_ERROR as u8,
+            format!("the goal ended with status {status:?}"),
+        ));
+    }
+    if result.error.error != TF2Error::NO_ERROR as u8 {
+        return Err(TfError::BufferServer(
+            result.error.error,
+            result.error.error_string,
+        ));
+    }
+    Ok(result.transform)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn result(error: u8) -> LookupResult {
+        let mut result = LookupResult::default();
+        result.error.error = error;
+        result.transform.child_frame_id = "base_link".to_string();
+        result
+    }
+
+    #[test]
+    fn test_goal_timeout() {
+        let timeout = goal_timeout(Duration::from_millis(1500));
+        assert_eq!((timeout.sec, timeout.nanosec), (1, 500_000_000));
+        let timeout = goal_timeout(Duration::MAX);
+        assert_eq!((timeout.sec, timeout.nanosec), (i32::MAX, 999_999_999));
+        assert!(duration_as_ns_i64(&timeout) > 0);
+    }
+
+    #[test]
+    fn test_transform_from_result() {
+        let tf = transform_from_result(GoalStatus::Succeeded, result(0));
+        assert_eq!(tf.unwrap().child_frame_id, "base_link");
+
+        let code = TF2Error::EXTRAPOLATION_ERROR as u8;
+        assert!(matches!(
+            transform_from_result(GoalStatus::Succeeded, result(code)),
+            Err(TfError::BufferServer(error, _)) if error == code
+        ));
+        assert!(matches!(
+            transform_from_result(GoalStatus::Canceled, result(0)),
+            Err(TfError::BufferServer(error, _)) if error == TF2Error::TRANSFORM_ERROR as u8
+        ));
+    }
+}
@@ -0,0 +1,256 @@
+use std::{mem, sync::Arc, time::Duration};
+
+use futures::{Stream, StreamExt};
+use r2r::{
+    builtin_interfaces::msg::Time,
+    geometry_msgs::msg::TransformStamped,
+    tf2_msgs::{
+        action::LookupTransform::{Action, Goal, Result as LookupResult},
+        msg::TF2Error,
+    },
+    ActionServerCancelRequest, ActionServerGoal, ActionServerGoalRequest,
+};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    task::JoinHandle,
+};
+
+use crate::{tf_error::TfError, tf_listener::TfListener, utils::duration_as_ns_i64};
+
+/// Maximum number of goals answered at the same time
+const MAX_ACTIVE_GOALS: usize = 100;
+
+/// Answers `tf2_msgs/LookupTransform` action goals with the transforms of a [`TfListener`], like
+/// `BufferServer` of tf2_ros
+///
+/// Nodes can then look up transforms through a [`BufferClient`](crate::BufferClient) instead of
+/// subscribing to `/tf` themselves. The `buffer_server` binary runs a server named
+/// `tf2_buffer_server`, like tf2_ros.
+///
+/// The goals are processed by tasks spawned on the current tokio runtime, and the node must be
+/// spun meanwhile:
+///
+/// ```no_run
+/// #[tokio::main]
+/// async fn main() {
+///     let ctx = r2r::Context::create().unwrap();
+///     let mut node = r2r::Node::create(ctx, "tf2_buffer_server", "").unwrap();
+///     let listener = tf_r2r::TfListener::new(&mut node);
+///     let _server = tf_r2r::BufferServer::new(&mut node, listener, "tf2_buffer_server").unwrap();
+///     tokio::task::spawn_blocking(move || loop {
+///         node.spin_once(std::time::Duration::from_millis(100));
+///     })
+///     .await
+///     .unwrap();
+/// }
+/// ```
+///
+/// Each goal is answered once its transform is available or its timeout elapses, or is canceled
+/// when requested. Like in tf2_ros, failed lookups still succeed the goal, with the error in the
+/// result. At most 100 goals are answered at the same time, and further goals are rejected.
+pub struct BufferServer {
+    task: JoinHandle<()>,
+}
+
+impl BufferServer {
+    /// Answers the goals sent to the action with the given name with the transforms of the
+    /// listener
+    ///
+    /// Returns an error if this is called outside of a tokio runtime.
+    pub fn new(
+        node: &mut r2r::Node,
+        listener: TfListener,
+        action_name: &str,
+    ) -> Result<Self, TfError> {
+        let runtime =
+            tokio::runtime::Handle::try_current().map_err(|err| TfError::R2r(err.to_string()))?;
+        let requests = node
+            .create_action_server::<Action>(action_name)
+            .map_err(|err| TfError::R2r(err.to_string()))?;
+        Ok(Self {
+            task: runtime.spawn(serve_goals(requests, listener)),
+        })
+    }
+}
+
+impl Drop for BufferServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Accepts the goals as soon as they arrive, and answers each of them from its own task. The goals
+/// are rejected while [`MAX_ACTIVE_GOALS`] goals are active.
+async fn serve_goals(
+    mut requests: impl Stream<Item = ActionServerGoalRequest<Action>> + Unpin,
+    listener: TfListener,
+) {
+    let active_goals = Arc::new(Semaphore::new(MAX_ACTIVE_GOALS));
+    while let Some(request) = requests.next().await {
+        let Ok(permit) = active_goals.clone().try_acquire_owned() else {
+            if let Err(err) = request.reject() {
+                r2r::log_warn!("tf_r2r", "failed to reject a LookupTransform goal: {err}");
+            }
+            continue;
+        };
+        match request.accept() {
+            Ok((goal, cancel_requests)) => {
+                tokio::spawn(answer_goal(listener.clone(), goal, cancel_requests, permit));
+            }
+            Err(err) => r2r::log_warn!("tf_r2r", "failed to accept a LookupTransform goal: {err}"),
+        }
+    }
+}
+
+/// Answers the goal, and then releases its permit
+async fn answer_goal(
+    listener: TfListener,
+    mut goal: ActionServerGoal<Action>,
+    mut cancel_requests: impl Stream<Item = ActionServerCancelRequest> + Unpin,
+    _permit: OwnedSemaphorePermit,
+) {
+    let request = goal.goal.clone();
+    let answered = tokio::select! {
+        result = lookup(&listener, &request) => goal.succeed(lookup_result(result)),
+        Some(cancel) = cancel_requests.next() => {
+            cancel.accept();
+            goal.cancel(LookupResult::default())
+        }
+    };
+    if let Err(err) = answered {
+        r2r::log_warn!("tf_r2r", "failed to answer a LookupTransform goal: {err}");
+    }
+}
+
+async fn lookup(listener: &TfListener, goal: &Goal) -> Result<TransformStamped, TfError> {
+    let timeout = Duration::from_nanos(duration_as_ns_i64(&goal.timeout).max(0) as u64);
+    if goal.advanced {
+        listener
+            .wait_for(timeout, || {
+                listener
+                    .lookup_transform_with_time_travel(
+                        &goal.target_frame,
+                        goal.target_time.clone(),
+                        &goal.source_frame,
+                        goal.source_time.clone(),
+                        &goal.fixed_frame,
+                    )
+                    .map(|transform| relabel_time_travel(transform, goal.target_time.clone()))
+            })
+            .await
+    } else {
+        listener
+            .wait_for_transform(
+                &goal.target_frame,
+                &goal.source_frame,
+                goal.source_time.clone(),
+                timeout,
+            )
+            .await
+    }
+}
+
+/// Labels the result of a time travel lookup like tf2, which expresses it in the target frame at
+/// the target time
+///
+/// [`TfListener::lookup_transform_with_time_travel`] computes the same transform as tf2, but labels
+/// it with the source frame and time.
+fn relabel_time_travel(mut transform: TransformStamped, target_time: Time) -> TransformStamped {
+    mem::swap(
+        &mut transform.header.frame_id,
+        &mut transform.child_frame_id,
+    );
+    transform.header.stamp = target_time;
+    transform
+}
+
+fn lookup_result(result: Result<TransformStamped, TfError>) -> LookupResult {
+    match result {
+        Ok(transform) => LookupResult {
+            transform,
+            error: TF2Error {
+                error: TF2Error::NO_ERROR as u8,
+                error_string: String::new(),
+            },
+        },
+        Err(err) => LookupResult {
+            transform: TransformStamped::default(),
+            error: TF2Error {
+                error: tf2_error_code(&err),
+                error_string: err.to_string(),
+            },
+        },
+    }
+}
+
+/// Returns the `tf2_msgs/TF2Error` code of the error
+fn tf2_error_code(err: &TfError) -> u8 {
+    let code = match err {
+        TfError::CouldNotFindTransform(..) | TfError::EmptyTransformChain(..) => {
+            TF2Error::LOOKUP_ERROR
+        }
+        TfError::AttemptedLookupInPast(..) | TfError::AttemptedLookUpInFuture(..) => {
+            TF2Error::EXTRAPOLATION_ERROR
+        }
+        TfError::InvalidArgument(..) => TF2Error::INVALID_ARGUMENT_ERROR,
+        TfError::BufferServer(code, _) => return *code,
+        _ => TF2Error::TRANSFORM_ERROR,
+    };
+    code as u8
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{test_utils::transform, tf_buffer::TfBuffer};
+
+    #[tokio::test]
+    async fn test_advanced_lookup() {
+        let mut buffer = TfBuffer::new();
+        for sec in [1, 2] {
+            let odom_to_base = transform("odom", "base_link", sec, sec.into());
+            buffer.set_transform(&odom_to_base, "", false).unwrap();
+            let base_to_camera = transform("base_link", "camera", sec, 0.5);
+            buffer.set_transform(&base_to_camera, "", false).unwrap();
+        }
+        let listener = TfListener::without_subscriptions(buffer);
+        let goal = Goal {
+            target_frame: "camera".to_string(),
+            target_time: Time { sec: 2, nanosec: 0 },
+            source_frame: "base_link".to_string(),
+            source_time: Time { sec: 1, nanosec: 0 },
+            fixed_frame: "odom".to_string(),
+            advanced: true,
+            ..Default::default()
+        };
+        let result = lookup_result(lookup(&listener, &goal).await);
+        assert_eq!(result.error.error, TF2Error::NO_ERROR as u8);
+        // Like in tf2, the result is expressed in the target frame at the target time.
+        assert_eq!(result.transform.header.frame_id, "camera");
+        assert_eq!(result.transform.child_frame_id, "base_link");
+        assert_eq!(result.transform.header.stamp, goal.target_time);
+        // base_link was at 1 at the source time, and the camera at 2.5 at the target time.
+        assert!((result.transform.transform.translation.x + 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lookup_result() {
+        let result = lookup_result(Ok(TransformStamped::default()));
+        assert_eq!(result.error.error, TF2Error::NO_ERROR as u8);
+
+        let err = TfError::CouldNotFindTransform("a".into(), "b".into(), HashMap::new());
+        let result = lookup_result(Err(err.clone()));
+        assert_eq!(result.error.error, TF2Error::LOOKUP_ERROR as u8);
+        assert_eq!(result.error.error_string, err.to_string());
+        assert_eq!(
+            tf2_error_code(&TfError::InvalidArgument(String::new())),
+            TF2Error::INVALID_ARGUMENT_ERROR as u8
+        );
+        assert_eq!(
+            tf2_error_code(&TfError::Cancelled),
+            TF2Error::TRANSFORM_ERROR as u8
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use r2r::builtin_interfaces::msg::Duration;
+
+/// Limits of the transforms stored for an edge of a [`TfBuffer`](crate::TfBuffer)
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheLimits {
+    /// How long transforms are kept, compared to the newest transform of the edge
+    pub duration: Duration,
+    /// Maximum number of transforms kept, or `None` for no limit. The newest transform is always
+    /// kept.
+    pub max_samples: Option<usize>,
+}
+
+impl CacheLimits {
+    /// Keeps the transforms for the duration, without limiting their number
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            max_samples: None,
+        }
+    }
+
+    /// Keeps at most `max_samples` transforms
+    pub fn with_max_samples(mut self, max_samples: usize) -> Self {
+        self.max_samples = Some(max_samples);
+        self
+    }
+}
+
+/// Cache limits of the edges whose child frame matches a pattern
+#[derive(Clone, Debug)]
+pub(crate) struct CacheOverride {
+    pattern: String,
+    pub(crate) limits: CacheLimits,
+}
+
+impl CacheOverride {
+    pub(crate) fn new(pattern: String, limits: CacheLimits) -> Self {
+        Self { pattern, limits }
+    }
+
+    pub(crate) fn matches(&self, frame_id: &str) -> bool {
+        matches_pattern(&self.pattern, frame_id)
+    }
+}
+
+/// Returns whether the frame ID matches the pattern, in which `*` matches any sequence of
+/// characters
+fn matches_pattern(pattern: &str, frame_id: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = frame_id.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<_> = parts.collect();
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("odom", "odom"));
+        assert!(!matches_pattern("odom", "odom_combined"));
+        assert!(matches_pattern("*", "odom"));
+        assert!(matches_pattern("*_joint", "elbow_joint"));
+        assert!(!matches_pattern("*_joint", "elbow_link"));
+        assert!(matches_pattern("arm/*", "arm/elbow_joint"));
+        assert!(matches_pattern("arm/*_joint", "arm/elbow_joint"));
+        assert!(matches_pattern("arm/*/*_joint", "arm/left/elbow_joint"));
+        assert!(!matches_pattern("arm/*/*_joint", "arm/elbow_joint"));
+        // The parts around a `*` do not overlap
+        assert!(!matches_pattern("a*a", "a"));
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use r2r::builtin_interfaces::msg::Time;
+
+use crate::tf_error::TfError;
+
+/// Source of the current time of a [`TfListener`](crate::TfListener)
+///
+/// The listener uses the clock to look up transforms at the current time, to measure the timeout
+/// of [`TfListener::wait_for_transform`](crate::TfListener::wait_for_transform) and to detect
+/// jumps backward in time. By default, it uses the ROS clock of its node, which follows `/clock`
+/// if the `use_sim_time` parameter of the node is set.
+///
+/// ```no_run
+/// let ctx = r2r::Context::create().unwrap();
+/// let mut node = r2r::Node::create(ctx, "listener", "").unwrap();
+/// let clock = tf_r2r::TfClock::system();
+/// let listener = tf_r2r::TfListener::builder()
+///     .clock(clock)
+///     .build_polling(&mut node)
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct TfClock(ClockSource);
+
+#[derive(Clone)]
+enum ClockSource {
+    Ros(Arc<Mutex<r2r::Clock>>),
+    System,
+}
+
+impl TfClock {
+    /// Uses the ROS clock of the node, which follows `/clock` if the `use_sim_time` parameter of
+    /// the node is set
+    pub fn ros(node: &r2r::Node) -> Self {
+        Self(ClockSource::Ros(node.get_ros_clock()))
+    }
+
+    /// Enables the simulated time of the node, and uses its ROS clock which then follows `/clock`
+    ///
+    /// This needs the `sim-time` feature, and r2r to be built with `rosgraph_msgs`.
+    #[cfg(feature = "sim-time")]
+    pub fn sim_time(node: &mut r2r::Node) -> Result<Self, TfError> {
+        r2r::assert_compiled_with_use_sim_time_support!();
+        node.get_time_source()
+            .enable_sim_time(node)
+            .map_err(|err| TfError::R2r(err.to_string()))?;
+        Ok(Self::ros(node))
+    }
+
+    /// Uses the system time, ignoring the simulated time
+    pub fn system() -> Self {
+        Self(ClockSource::System)
+    }
+
+    /// Returns the current time
+    pub fn now(&self) -> Result<Time, TfError> {
+        let now = self.now_since_epoch()?;
+        Ok(Time {
+            sec: now.as_secs() as i32,
+            nanosec: now.subsec_nanos(),
+        })
+    }
+
+    pub(crate) fn now_since_epoch(&self) -> Result<Duration, TfError> {
+        match &self.0 {
+            ClockSource::Ros(clock) => clock
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get_now()
+                .map_err(|err| TfError::R2r(err.to_string())),
+            ClockSource::System => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|err| TfError::R2r(err.to_string())),
+        }
+    }
+}
+
+impl fmt::Debug for TfClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            ClockSource::Ros(_) => f.write_str("TfClock::Ros"),
+            ClockSource::System => f.write_str("TfClock::System"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::time_as_ns_i64;
+
+    #[test]
+    fn test_system_clock() {
+        let clock = TfClock::system();
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as i64;
+        let now = time_as_ns_i64(&clock.now().unwrap());
+        assert!(now >= before);
+        assert!(now - before < 1_000_000_000);
+    }
+}
@@ -0,0 +1,243 @@
+use std::{collections::VecDeque, fmt, sync::Arc};
+
+use r2r::{
+    builtin_interfaces::msg::{Duration, Time},
+    geometry_msgs::msg::TransformStamped,
+};
+
+use crate::{
+    transforms::isometry_from_transform,
+    utils::{duration_as_ns_i64, time_as_ns_i64},
+};
+
+/// Maximum number of conflicts kept by a [`TfBuffer`](crate::TfBuffer)
+const MAX_CONFLICTS: usize = 100;
+
+/// Differences above which the transforms of two authorities for the same edge are reported as
+/// [`TfConflict::CompetingAuthorities`] by a [`TfBuffer`](crate::TfBuffer)
+///
+/// The default tolerances are 1 second, 0.1 meters and 0.1 radians.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConflictTolerances {
+    /// Dynamic transforms are only compared if their stamps are at most this far apart. Static
+    /// transforms are always compared.
+    pub time: Duration,
+    /// Minimum difference of the translations in meters
+    pub translation: f64,
+    /// Minimum difference of the rotations in radians
+    pub rotation: f64,
+}
+
+impl Default for ConflictTolerances {
+    fn default() -> Self {
+        Self {
+            time: Duration { sec: 1, nanosec: 0 },
+            translation: 0.1,
+            rotation: 0.1,
+        }
+    }
+}
+
+/// A suspicious transform detected by a [`TfBuffer`](crate::TfBuffer)
+///
+/// Conflicting transforms are inserted anyway, unless they are repeated or late transforms dropped
+/// according to the [`SamplePolicy`](crate::SamplePolicy), so each conflict means that the tree may
+/// have been silently rewritten.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum TfConflict {
+    /// The child frame was published with a different parent than before at the stamp
+    Reparented {
+        child: String,
+        previous_parent: String,
+        parent: String,
+        stamp: Time,
+        authority: String,
+    },
+    /// Two authorities published the same edge with significantly different transforms at close
+    /// stamps
+    CompetingAuthorities {
+        parent: String,
+        child: String,
+        stamp: Time,
+        previous_authority: String,
+        authority: String,
+    },
+    /// A transform with the same stamp as a stored one was received, like TF_REPEATED_DATA in tf2
+    RepeatedData {
+        parent: String,
+        child: String,
+        stamp: Time,
+        authority: String,
+    },
+    /// A transform older than the cache duration was received, like TF_OLD_DATA in tf2
+    OldData {
+        parent: String,
+        child: String,
+        stamp: Time,
+        authority: String,
+    },
+}
+
+impl TfConflict {
+    pub(crate) fn repeated_data(transform: &TransformStamped, authority: &str) -> Self {
+        Self::RepeatedData {
+            parent: transform.header.frame_id.clone(),
+            child: transform.child_frame_id.clone(),
+            stamp: transform.header.stamp.clone(),
+            authority: authority.to_string(),
+        }
+    }
+
+    pub(crate) fn old_data(transform: &TransformStamped, authority: &str) -> Self {
+        Self::OldData {
+            parent: transform.header.frame_id.clone(),
+            child: transform.child_frame_id.clone(),
+            stamp: transform.header.stamp.clone(),
+            authority: authority.to_string(),
+        }
+    }
+
+    /// Returns the conflict if `transform` of `authority` competes with the `previous` transform
+    /// of another authority
+    pub(crate) fn competing_authorities(
+        previous: &TransformStamped,
+        previous_authority: &str,
+        transform: &TransformStamped,
+        authority: &str,
+        static_tf: bool,
+        tolerances: &ConflictTolerances,
+    ) -> Option<Self> {
+        if previous_authority == authority {
+            return None;
+        }
+        let elapsed =
+            time_as_ns_i64(&transform.header.stamp) - time_as_ns_i64(&previous.header.stamp);
+        if !static_tf && elapsed.abs() > duration_as_ns_i64(&tolerances.time) {
+            return None;
+        }
+        let previous_isometry = isometry_from_transform(&previous.transform);
+        let isometry = isometry_from_transform(&transform.transform);
+        let differs = (isometry.translation.vector - previous_isometry.translation.vector).norm()
+            > tolerances.translation
+            || isometry.rotation.angle_to(&previous_isometry.rotation) > tolerances.rotation;
+        differs.then(|| Self::CompetingAuthorities {
+            parent: transform.header.frame_id.clone(),
+            child: transform.child_frame_id.clone(),
+            stamp: transform.header.stamp.clone(),
+            previous_authority: previous_authority.to_string(),
+            authority: authority.to_string(),
+        })
+    }
+}
+
+/// Callback of a [`TfBuffer`](crate::TfBuffer) which is called for every detected conflict
+#[derive(Clone)]
+pub(crate) struct ConflictCallback(Arc<dyn Fn(&TfConflict) + Send + Sync>);
+
+impl fmt::Debug for ConflictCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ConflictCallback")
+    }
+}
+
+/// Keeps the latest conflicts, and passes new ones to the callback
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ConflictMonitor {
+    conflicts: VecDeque<TfConflict>,
+    callback: Option<ConflictCallback>,
+}
+
+impl ConflictMonitor {
+    pub(crate) fn set_callback(&mut self, callback: impl Fn(&TfConflict) + Send + Sync + 'static) {
+        self.callback = Some(ConflictCallback(Arc::new(callback)));
+    }
+
+    pub(crate) fn report(&mut self, conflict: TfConflict) {
+        if let Some(callback) = &self.callback {
+            (callback.0)(&conflict);
+        }
+        if self.conflicts.len() == MAX_CONFLICTS {
+            self.conflicts.pop_front();
+        }
+        self.conflicts.push_back(conflict);
+    }
+
+    pub(crate) fn conflicts(&self) -> &VecDeque<TfConflict> {
+        &self.conflicts
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.conflicts.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::test_utils::transform;
+
+    #[test]
+    fn test_competing_authorities() {
+        let previous = transform("odom", "base_link", 10, 0.0);
+        let competing = |tf: &TransformStamped, authority: &str, static_tf: bool| {
+            TfConflict::competing_authorities(
+                &previous,
+                "/node1",
+                tf,
+                authority,
+                static_tf,
+                &ConflictTolerances::default(),
+            )
+        };
+        assert!(competing(&transform("odom", "base_link", 10, 1.0), "/node2", false).is_some());
+        // Same authority
+        assert!(competing(&transform("odom", "base_link", 10, 1.0), "/node1", false).is_none());
+        // Small difference
+        assert!(competing(&transform("odom", "base_link", 10, 0.05), "/node2", false).is_none());
+        // Distant stamps only matter for dynamic transforms
+        assert!(competing(&transform("odom", "base_link", 12, 1.0), "/node2", false).is_none());
+        assert!(competing(&transform("odom", "base_link", 12, 1.0), "/node2", true).is_some());
+
+        let tolerances = ConflictTolerances {
+            translation: 0.01,
+            ..Default::default()
+        };
+        assert!(TfConflict::competing_authorities(
+            &previous,
+            "/node1",
+            &transform("odom", "base_link", 10, 0.05),
+            "/node2",
+            false,
+            &tolerances,
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn test_conflict_monitor() {
+        let reported = Arc::new(Mutex::new(0));
+        let mut monitor = ConflictMonitor::default();
+        {
+            let reported = reported.clone();
+            monitor.set_callback(move |_| *reported.lock().unwrap() += 1);
+        }
+        for sec in 0..MAX_CONFLICTS as i32 + 10 {
+            monitor.report(TfConflict::old_data(
+                &transform("odom", "base_link", sec, 0.0),
+                "/node1",
+            ));
+        }
+        assert_eq!(*reported.lock().unwrap(), MAX_CONFLICTS + 10);
+        assert_eq!(monitor.conflicts().len(), MAX_CONFLICTS);
+        // The oldest conflicts are dropped first
+        assert!(matches!(
+            monitor.conflicts().front(),
+            Some(TfConflict::OldData { stamp, .. }) if stamp.sec == 10
+        ));
+        monitor.clear();
+        assert!(monitor.conflicts().is_empty());
+    }
+}
@@ -8,17 +8,43 @@ use thiserror::Error;
 #[non_exhaustive]
 pub enum TfError {
     /// Error due to looking up too far in the past. I.E the information is no longer available in the TF Cache.
+    ///
+    /// Contains the oldest stored transform. The authority which published it is returned by
+    /// [`TfBuffer::error_authority`](crate::TfBuffer::error_authority).
     #[error("tf_r2r: AttemptedLookupInPast {:?} < {:?}",.0, .1)]
     AttemptedLookupInPast(Time, Box<TransformStamped>),
     /// Error due to the transform not yet being available.
+    ///
+    /// Contains the newest stored transform. The authority which published it is returned by
+    /// [`TfBuffer::error_authority`](crate::TfBuffer::error_authority).
     #[error("tf_r2r: AttemptedLookupInFuture {:?} < {:?}",.0, .1)]
     AttemptedLookUpInFuture(Box<TransformStamped>, Time),
     /// There is no path between the from and to frame.
     #[error("tf_r2r: CouldNotFindTransform {} -> {} ({:?})", .0, .1, .2)]
     CouldNotFindTransform(String, String, HashMap<String, HashSet<String>>),
+    /// No transforms are stored between the parent and child frame.
+    #[error("tf_r2r: EmptyTransformChain {} -> {}", .0, .1)]
+    EmptyTransformChain(String, String),
+    /// A dynamic transform with the same stamp as a stored one was rejected.
+    #[error("tf_r2r: RepeatedData {} -> {} at {:?}", .0, .1, .2)]
+    RepeatedData(String, String, Time),
+    /// A dynamic transform older than the cache duration was rejected.
+    #[error("tf_r2r: OldData {} -> {} at {:?}", .0, .1, .2)]
+    OldData(String, String, Time),
     /// In the event that a write is simultaneously happening with a read of the same tf buffer
     #[error("tf_r2r: CouldNotAcquireLock")]
     CouldNotAcquireLock,
+    /// An argument passed to the API was invalid.
+    #[error("tf_r2r: InvalidArgument {}", .0)]
+    InvalidArgument(String),
+    /// The listener was shut down while waiting for a transform.
+    #[error("tf_r2r: Cancelled")]
+    Cancelled,
+    /// A buffer server answered a lookup with an error.
+    ///
+    /// Contains the error code of `tf2_msgs/TF2Error` and its description.
+    #[error("tf_r2r: BufferServer error {}: {}", .0, .1)]
+    BufferServer(u8, String),
     /// Error of r2r
     #[error("tf_r2r: r2r error {:?}", .0)]
     R2r(String),
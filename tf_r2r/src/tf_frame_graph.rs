@@ -0,0 +1,225 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{tf_buffer::FrameInfo, tf_error::TfError};
+
+/// A frame of the tree of another node, as answered by its `tf2_msgs/FrameGraph` service
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameDescription {
+    /// ID of the frame
+    pub frame_id: String,
+    /// ID of the current parent of the frame
+    pub parent: String,
+    /// Authority of the newest transform from the parent, usually the name of the node which
+    /// published it
+    pub authority: String,
+    /// Stored transforms per second
+    pub rate: f64,
+    /// Stamp of the newest stored transform in seconds
+    pub newest_stamp: f64,
+    /// Stamp of the oldest stored transform in seconds
+    pub oldest_stamp: f64,
+    /// Time between the oldest and newest stored transforms in seconds
+    pub buffer_length: f64,
+}
+
+/// Parses the frames described in YAML by [`TfBuffer::all_frames_as_yaml`](crate::TfBuffer::all_frames_as_yaml)
+/// or by `allFramesAsYAML` of tf2
+pub(crate) fn parse_frames_yaml(yaml: &str) -> Result<Vec<FrameDescription>, TfError> {
+    let mut frames: Vec<FrameDescription> = Vec::new();
+    for line in yaml.lines() {
+        if line.trim().is_empty() || line.trim() == "[]" {
+            continue;
+        }
+        let invalid = || TfError::InvalidArgument(format!("invalid frame graph line {line:?}"));
+        if !line.starts_with(' ') {
+            let frame_id = line.trim_end().strip_suffix(':').ok_or_else(invalid)?;
+            frames.push(FrameDescription {
+                frame_id: unquote(frame_id),
+                ..Default::default()
+            });
+            continue;
+        }
+        let (key, value) = line.trim().split_once(':').ok_or_else(invalid)?;
+        let value = unquote(value.trim());
+        let frame = frames.last_mut().ok_or_else(invalid)?;
+        let number = || value.parse::<f64>().map_err(|_| invalid());
+        match key {
+            "parent" => frame.parent = value,
+            "broadcaster" => frame.authority = value,
+            "rate" => frame.rate = number()?,
+            "most_recent_transform" => frame.newest_stamp = number()?,
+            "oldest_transform" => frame.oldest_stamp = number()?,
+            "buffer_length" => frame.buffer_length = number()?,
+            // e.g. `transform_delay`, which tf2 adds when the current time is given
+            _ => {}
+        }
+    }
+    Ok(frames)
+}
+
+fn unquote(value: &str) -> String {
+    if let Some(value) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return value.replace("''", "'");
+    }
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+/// Quotes the text as a single-quoted YAML scalar, so that frame IDs containing e.g. `:` or `#`
+/// are read back unchanged
+pub(crate) fn quote_yaml(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Renders the frames as a Graphviz DOT graph, like `view_frames` of tf2
+pub(crate) fn to_dot(infos: &[FrameInfo]) -> String {
+    let mut dot = "digraph G {\n".to_string();
+    for info in infos {
+        let _ = writeln!(
+            dot,
+            "\"{}\" -> \"{}\"[label=\"{}\"];",
+            escape_dot(&info.parent),
+            escape_dot(&info.frame_id),
+            edge_label(info, "\\n", escape_dot),
+        );
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Renders the frames as a Mermaid flowchart
+pub(crate) fn to_mermaid(infos: &[FrameInfo]) -> String {
+    // Mermaid node IDs cannot contain most characters of frame IDs, so the frames are numbered.
+    let mut nodes = BTreeMap::new();
+    for info in infos {
+        nodes.insert(info.parent.as_str(), 0);
+        nodes.insert(info.frame_id.as_str(), 0);
+    }
+    let mut mermaid = "graph TD\n".to_string();
+    for (index, (frame_id, id)) in nodes.iter_mut().enumerate() {
+        *id = index;
+        let _ = writeln!(mermaid, "    n{index}[\"{}\"]", escape_mermaid(frame_id));
+    }
+    for info in infos {
+        let _ = writeln!(
+            mermaid,
+            "    n{} -->|\"{}\"| n{}",
+            nodes[info.parent.as_str()],
+            edge_label(info, "<br>", escape_mermaid),
+            nodes[info.frame_id.as_str()],
+        );
+    }
+    mermaid
+}
+
+/// Describes the edge from the parent of the frame in lines joined by `separator`
+fn edge_label(info: &FrameInfo, separator: &str, escape: fn(&str) -> String) -> String {
+    [
+        format!("Broadcaster: {}", escape(&info.authority)),
+        format!("Average rate: {:.3} Hz", info.rate),
+        format!("Most recent transform: {:.3}", info.newest_stamp),
+        format!(
+            "Buffer length: {:.3} s ({} transforms)",
+            info.newest_stamp - info.oldest_stamp,
+            info.samples
+        ),
+    ]
+    .join(separator)
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn infos() -> Vec<FrameInfo> {
+        vec![
+            FrameInfo {
+                frame_id: "base_link".to_string(),
+                parent: "odom".to_string(),
+                authority: "/odometry".to_string(),
+                oldest_stamp: 1.0,
+                newest_stamp: 3.0,
+                samples: 3,
+                rate: 1.5,
+            },
+            FrameInfo {
+                frame_id: "odom".to_string(),
+                parent: "map".to_string(),
+                authority: "/\"slam\"".to_string(),
+                oldest_stamp: 0.0,
+                newest_stamp: 0.0,
+                samples: 1,
+                rate: 10000.0,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_to_dot() {
+        assert_eq!(
+            to_dot(&infos()),
+            "digraph G {\n\
+             \"odom\" -> \"base_link\"[label=\"Broadcaster: /odometry\\nAverage rate: 1.500 Hz\\n\
+             Most recent transform: 3.000\\nBuffer length: 2.000 s (3 transforms)\"];\n\
+             \"map\" -> \"odom\"[label=\"Broadcaster: /\\\"slam\\\"\\nAverage rate: 10000.000 Hz\\n\
+             Most recent transform: 0.000\\nBuffer length: 0.000 s (1 transforms)\"];\n\
+             }\n"
+        );
+        assert_eq!(to_dot(&[]), "digraph G {\n}\n");
+    }
+
+    #[test]
+    fn test_parse_frames_yaml() {
+        let yaml = "base_link: \n  parent: 'odom'\n  broadcaster: '/odometry'\n  rate: 1.500\n  \
+                    most_recent_transform: 3.000\n  oldest_transform: 1.000\n  \
+                    transform_delay: 0.500\n  buffer_length: 2.000\n\
+                    odom: \n  parent: 'map'\n  broadcaster: '/slam'\n  rate: 10000.000\n  \
+                    most_recent_transform: 0.000\n  oldest_transform: 0.000\n  \
+                    buffer_length: 0.000\n";
+        let frames = parse_frames_yaml(yaml).unwrap();
+        assert_eq!(
+            frames[0],
+            FrameDescription {
+                frame_id: "base_link".to_string(),
+                parent: "odom".to_string(),
+                authority: "/odometry".to_string(),
+                rate: 1.5,
+                newest_stamp: 3.0,
+                oldest_stamp: 1.0,
+                buffer_length: 2.0,
+            }
+        );
+        assert_eq!(frames[1].frame_id, "odom");
+        assert_eq!(frames[1].parent, "map");
+
+        assert!(parse_frames_yaml("[]").unwrap().is_empty());
+        assert!(parse_frames_yaml("  parent: 'odom'\n").is_err());
+        assert!(parse_frames_yaml("odom: \n  rate: fast\n").is_err());
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        assert_eq!(
+            to_mermaid(&infos()),
+            "graph TD\n    \
+             n0[\"base_link\"]\n    \
+             n1[\"map\"]\n    \
+             n2[\"odom\"]\n    \
+             n2 -->|\"Broadcaster: /odometry<br>Average rate: 1.500 Hz<br>\
+             Most recent transform: 3.000<br>Buffer length: 2.000 s (3 transforms)\"| n0\n    \
+             n1 -->|\"Broadcaster: /#quot;slam#quot;<br>Average rate: 10000.000 Hz<br>\
+             Most recent transform: 0.000<br>Buffer length: 0.000 s (1 transforms)\"| n2\n"
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use r2r::{tf2_msgs::srv::FrameGraph, QosProfile};
+
+use crate::{
+    tf_error::TfError,
+    tf_frame_graph::{parse_frames_yaml, FrameDescription},
+};
+
+/// Fetches the frame tree of another node through its `tf2_msgs/FrameGraph` service, like
+/// `rqt_tf_tree` does
+///
+/// The node must be spun while waiting for the service and for the answer:
+///
+/// ```no_run
+/// #[tokio::main]
+/// async fn main() {
+///     let ctx = r2r::Context::create().unwrap();
+///     let mut node = r2r::Node::create(ctx, "frame_graph_client", "").unwrap();
+///     let client = tf_r2r::FrameGraphClient::new(&mut node, "/tf2_frames").unwrap();
+///     tokio::task::spawn_blocking(move || loop {
+///         node.spin_once(std::time::Duration::from_millis(100));
+///     });
+///     client.wait_for_service().await.unwrap();
+///     for frame in client.fetch().await.unwrap() {
+///         println!("{} -> {}", frame.parent, frame.frame_id);
+///     }
+/// }
+/// ```
+pub struct FrameGraphClient {
+    client: r2r::Client<FrameGraph::Service>,
+}
+
+impl FrameGraphClient {
+    /// Creates a client of the service with the given name
+    pub fn new(node: &mut r2r::Node, service: &str) -> Result<Self, TfError> {
+        let client = node
+            .create_client::<FrameGraph::Service>(service, QosProfile::services_default())
+            .map_err(|err| TfError::R2r(err.to_string()))?;
+        Ok(Self { client })
+    }
+
+    /// Waits until the service is available
+    pub async fn wait_for_service(&self) -> Result<(), TfError> {
+        r2r::Node::is_available(&self.client)
+            .map_err(|err| TfError::R2r(err.to_string()))?
+            .await
+            .map_err(|err| TfError::R2r(err.to_string()))
+    }
+
+    /// Calls the service, and returns the frames of the answered tree
+    pub async fn fetch(&self) -> Result<Vec<FrameDescription>, TfError> {
+        let response = self
+            .client
+            .request(&FrameGraph::Request {})
+            .map_err(|err| TfError::R2r(err.to_string()))?
+            .await
+            .map_err(|err| TfError::R2r(err.to_string()))?;
+        parse_frames_yaml(&response.frame_yaml)
+    }
+}
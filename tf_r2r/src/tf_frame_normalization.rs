@@ -0,0 +1,139 @@
+use std::borrow::Cow;
+
+/// How a [`TfBuffer`](crate::TfBuffer) normalizes frame IDs when transforms are inserted and
+/// looked up
+///
+/// By default, only leading slashes are stripped, like tf2 does, so that `/base_link` and
+/// `base_link` are the same frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameNormalization {
+    /// Strip leading slashes, e.g. `/base_link` becomes `base_link`
+    pub strip_leading_slash: bool,
+    /// How the tf_prefix of frame IDs is handled
+    pub tf_prefix: TfPrefix,
+}
+
+/// How a [`FrameNormalization`] handles the tf_prefix of frame IDs
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TfPrefix {
+    /// Leave the frame IDs as they are
+    #[default]
+    Keep,
+    /// Prepend the prefix to all frame IDs which do not start with it, e.g. `base_link` becomes
+    /// `robot1/base_link`
+    Apply(String),
+    /// Remove the prefix from all frame IDs which start with it, e.g. `robot1/base_link` becomes
+    /// `base_link`
+    Strip(String),
+}
+
+impl Default for FrameNormalization {
+    fn default() -> Self {
+        Self {
+            strip_leading_slash: true,
+            tf_prefix: TfPrefix::Keep,
+        }
+    }
+}
+
+impl FrameNormalization {
+    /// Leaves all frame IDs as they are
+    pub fn none() -> Self {
+        Self {
+            strip_leading_slash: false,
+            tf_prefix: TfPrefix::Keep,
+        }
+    }
+
+    /// Returns the normalized frame ID
+    pub fn normalize<'a>(&self, frame_id: &'a str) -> Cow<'a, str> {
+        let frame_id = if self.strip_leading_slash {
+            frame_id.trim_start_matches('/')
+        } else {
+            frame_id
+        };
+        match &self.tf_prefix {
+            TfPrefix::Keep => Cow::Borrowed(frame_id),
+            TfPrefix::Apply(prefix) => {
+                let prefix = prefix.trim_matches('/');
+                if prefix.is_empty() || frame_id.is_empty() || has_prefix(frame_id, prefix) {
+                    Cow::Borrowed(frame_id)
+                } else {
+                    let frame_id = frame_id.trim_start_matches('/');
+                    Cow::Owned(format!("{prefix}/{frame_id}"))
+                }
+            }
+            TfPrefix::Strip(prefix) => {
+                let prefix = prefix.trim_matches('/');
+                if prefix.is_empty() || !has_prefix(frame_id, prefix) {
+                    Cow::Borrowed(frame_id)
+                } else {
+                    Cow::Borrowed(&frame_id.trim_start_matches('/')[prefix.len() + 1..])
+                }
+            }
+        }
+    }
+}
+
+/// Returns `true` if the frame ID starts with `prefix/`, ignoring leading slashes
+fn has_prefix(frame_id: &str, prefix: &str) -> bool {
+    frame_id
+        .trim_start_matches('/')
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_strip_leading_slash() {
+        let normalization = FrameNormalization::default();
+        assert_eq!(normalization.normalize("/base_link"), "base_link");
+        assert_eq!(normalization.normalize("//base_link"), "base_link");
+        assert_eq!(normalization.normalize("base_link"), "base_link");
+        assert_eq!(normalization.normalize("/"), "");
+
+        let normalization = FrameNormalization::none();
+        assert_eq!(normalization.normalize("/base_link"), "/base_link");
+    }
+
+    #[test]
+    fn test_apply_tf_prefix() {
+        let normalization = FrameNormalization {
+            strip_leading_slash: true,
+            tf_prefix: TfPrefix::Apply("/robot1/".to_string()),
+        };
+        assert_eq!(normalization.normalize("base_link"), "robot1/base_link");
+        assert_eq!(normalization.normalize("/base_link"), "robot1/base_link");
+        assert_eq!(
+            normalization.normalize("robot1/base_link"),
+            "robot1/base_link"
+        );
+        assert_eq!(
+            normalization.normalize("/robot1/base_link"),
+            "robot1/base_link"
+        );
+        assert_eq!(
+            normalization.normalize("robot10/base_link"),
+            "robot1/robot10/base_link"
+        );
+    }
+
+    #[test]
+    fn test_strip_tf_prefix() {
+        let normalization = FrameNormalization {
+            strip_leading_slash: false,
+            tf_prefix: TfPrefix::Strip("robot1".to_string()),
+        };
+        assert_eq!(normalization.normalize("robot1/base_link"), "base_link");
+        assert_eq!(normalization.normalize("/robot1/base_link"), "base_link");
+        assert_eq!(
+            normalization.normalize("robot10/base_link"),
+            "robot10/base_link"
+        );
+        assert_eq!(normalization.normalize("/base_link"), "/base_link");
+        assert_eq!(normalization.normalize("robot1"), "robot1");
+    }
+}
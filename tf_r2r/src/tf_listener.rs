@@ -1,66 +1,231 @@
 use std::{
-    sync::{Arc, RwLock},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
     time::Duration,
 };
 
-use futures::StreamExt;
+use futures::{FutureExt, Stream, StreamExt};
 use r2r::{
-    builtin_interfaces::msg::Time, geometry_msgs::msg::TransformStamped, tf2_msgs::msg::TFMessage,
-    QosProfile,
+    builtin_interfaces::msg::Time,
+    geometry_msgs::msg::TransformStamped,
+    tf2_msgs::{msg::TFMessage, srv::FrameGraph},
+    QosProfile, ServiceRequest,
+};
+use tokio::{
+    sync::{watch, Notify},
+    task::JoinHandle,
 };
 
-use crate::{tf_buffer::TfBuffer, tf_error::TfError};
+use crate::{
+    qos::{dynamic_listener_qos, static_listener_qos},
+    tf_buffer::TfBuffer,
+    tf_clock::TfClock,
+    tf_error::TfError,
+    tf_shared_buffer::SharedTfBuffer,
+    utils::time_as_ns_i64,
+};
 
+/// Use this struct to lookup transforms.
+///
+/// By default, the subscriptions are processed by tasks spawned on the ambient tokio runtime.
+/// Nodes which spin without a tokio runtime can use [`TfListenerBuilder::build_polling`] and call
+/// [`TfListener::spin_once`] or [`TfListener::poll`] in their loop instead:
+///
+/// ```no_run
+/// let ctx = r2r::Context::create().unwrap();
+/// let mut node = r2r::Node::create(ctx, "listener", "").unwrap();
+/// let listener = tf_r2r::TfListener::builder().build_polling(&mut node).unwrap();
+/// loop {
+///     listener.spin_once(&mut node, std::time::Duration::from_millis(100));
+///     let tf = listener.lookup_transform("camera", "base_link", Default::default());
+///     println!("{tf:?}");
+/// }
+/// ```
+///
+/// The listener is cheap to clone, and all clones share the same subscriptions and buffer, so that
+/// several components can use a single subscription to `/tf` instead of creating a listener each.
+/// The subscriptions are closed when the last clone is dropped or [`TfListener::shutdown`] is
+/// called.
+///
+/// When the ROS time of the node jumps backward, e.g. when a bag is played in a loop, the dynamic
+/// transforms are removed from the buffer.
+///
+/// If [`TfListenerBuilder::frame_graph_service`] is set, the listener answers `tf2_msgs/FrameGraph`
+/// requests with [`TfBuffer::all_frames_as_yaml`], so that tools like `rqt_tf_tree` can show its
+/// tree.
+#[derive(Clone)]
 pub struct TfListener {
-    buffer: Arc<RwLock<TfBuffer>>,
+    state: Arc<ListenerState>,
+    dynamic_statistics: Arc<Mutex<TopicStatistics>>,
+    static_statistics: Arc<Mutex<TopicStatistics>>,
+    subscriptions: Arc<Subscriptions>,
+}
+
+/// Processes the subscriptions and shuts down the listener when the last clone is dropped
+struct Subscriptions {
+    state: Arc<ListenerState>,
+    pollers: Mutex<Vec<Poller>>,
+    frame_graph: Mutex<Option<FrameGraphStream>>,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+    // Sending on or dropping the sender stops the subscriber tasks.
+    shutdown: watch::Sender<bool>,
+}
+
+/// State shared between the listener and its subscriptions
+struct ListenerState {
+    buffer: Arc<SharedTfBuffer>,
+    is_shutdown: AtomicBool,
+    // Notified every time new transforms are received, and on shutdown.
+    updated: Notify,
+    clock: TfClock,
+    // Time of the clock when transforms were last received, to detect jumps backward in time
+    last_update: Mutex<Option<Duration>>,
+}
+
+/// Statistics of the messages received on a tf topic
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TopicStatistics {
+    /// Number of received messages
+    pub received: u64,
+    /// Number of dynamic messages which were older than the cache duration of the buffer when
+    /// they arrived
+    ///
+    /// These messages are passed to the buffer anyway, which decides which transforms it keeps.
+    pub late: u64,
+    /// Number of received transforms which the buffer did not store, because they were invalid,
+    /// or rejected or ignored by its [`SamplePolicy`](crate::SamplePolicy)
+    pub dropped: u64,
+    /// Difference between the arrival time and the newest stamp of the last message, measured
+    /// with the clock of the listener
+    pub last_lag: Option<Duration>,
+    /// Largest lag observed so far
+    pub max_lag: Option<Duration>,
 }
 
 impl TfListener {
     /// Create a new TfListener
+    ///
+    /// # Panics
+    ///
+    /// Panics if the subscribers could not be created or if this is called outside of a tokio
+    /// runtime. Use [`TfListener::try_new`] to handle the error instead.
     #[track_caller]
     pub fn new(node: &mut r2r::Node) -> Self {
         Self::new_with_buffer(node, TfBuffer::new())
     }
 
+    /// Create a new TfListener which stores the received transforms in the given buffer
+    ///
+    /// # Panics
+    ///
+    /// Panics if the subscribers could not be created or if this is called outside of a tokio
+    /// runtime. Use [`TfListener::try_new_with_buffer`] to handle the error instead.
     #[track_caller]
     pub fn new_with_buffer(node: &mut r2r::Node, tf_buffer: TfBuffer) -> Self {
-        let buff = Arc::new(RwLock::new(tf_buffer));
+        Self::try_new_with_buffer(node, tf_buffer).unwrap()
+    }
 
-        let mut dynamic_subscriber = node
-            .subscribe::<TFMessage>("/tf", QosProfile::default())
-            .unwrap();
+    /// Create a new TfListener which stores the received transforms in the given buffer,
+    /// returning an error if the subscribers could not be created or if this is called outside
+    /// of a tokio runtime.
+    pub fn try_new_with_buffer(node: &mut r2r::Node, tf_buffer: TfBuffer) -> Result<Self, TfError> {
+        Self::builder().buffer(tf_buffer).build(node)
+    }
 
-        let buff_for_dynamic_sub = buff.clone();
-        tokio::spawn(async move {
-            while Arc::strong_count(&buff_for_dynamic_sub) > 1 {
-                if let Some(tf) = dynamic_subscriber.next().await {
-                    buff_for_dynamic_sub
-                        .write()
-                        .unwrap()
-                        .handle_incoming_transforms(tf, false);
-                }
-                tokio::time::sleep(Duration::from_millis(100)).await;
-            }
-        });
+    /// Create a new TfListener which stores the received transforms in the given buffer
+    ///
+    /// The buffer may be shared with other listeners or components.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the subscribers could not be created or if this is called outside of a tokio
+    /// runtime. Use [`TfListenerBuilder::shared_buffer`] to handle the error instead.
+    #[track_caller]
+    pub fn new_with_shared_buffer(node: &mut r2r::Node, tf_buffer: Arc<SharedTfBuffer>) -> Self {
+        Self::builder()
+            .shared_buffer(tf_buffer)
+            .build(node)
+            .unwrap()
+    }
 
-        let mut static_subscriber = node
-            .subscribe::<TFMessage>("/tf_static", QosProfile::default())
-            .unwrap();
+    /// Create a new TfListener, returning an error if the subscribers could not be created or if
+    /// this is called outside of a tokio runtime.
+    pub fn try_new(node: &mut r2r::Node) -> Result<Self, TfError> {
+        Self::builder().build(node)
+    }
 
-        let buff_for_static_sub = buff.clone();
-        tokio::spawn(async move {
-            while Arc::strong_count(&buff_for_static_sub) > 1 {
-                if let Some(tf) = static_subscriber.next().await {
-                    buff_for_static_sub
-                        .write()
-                        .unwrap()
-                        .handle_incoming_transforms(tf, true);
+    /// Returns a builder to configure the topics and queue size of a TfListener
+    pub fn builder() -> TfListenerBuilder {
+        TfListenerBuilder::default()
+    }
+
+    /// Processes all messages which were received by the node so far, and returns their number.
+    /// Also answers the pending `FrameGraph` requests, which are not counted.
+    ///
+    /// This is only needed for listeners created by [`TfListenerBuilder::build_polling`], and does
+    /// nothing otherwise.
+    pub fn poll(&self) -> usize {
+        let mut pollers = self
+            .subscriptions
+            .pollers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut count = 0;
+        pollers.retain_mut(|poller| loop {
+            match poller.subscriber.next().now_or_never() {
+                Some(Some(msg)) => {
+                    poller.ingestor.ingest(msg);
+                    count += 1;
                 }
-                tokio::time::sleep(Duration::from_millis(100)).await;
+                // The subscription was closed.
+                Some(None) => break false,
+                None => break true,
             }
         });
+        drop(pollers);
+        let mut frame_graph = self
+            .subscriptions
+            .frame_graph
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        while let Some(requests) = frame_graph.as_mut() {
+            match requests.next().now_or_never() {
+                Some(Some(request)) => answer_frame_graph(&self.state, request),
+                Some(None) => *frame_graph = None,
+                None => break,
+            }
+        }
+        count
+    }
+
+    /// Spins the node once and processes the received messages. Returns the number of processed
+    /// messages.
+    pub fn spin_once(&self, node: &mut r2r::Node, timeout: Duration) -> usize {
+        node.spin_once(timeout);
+        self.poll()
+    }
 
-        TfListener { buffer: buff }
+    /// Returns the statistics of the messages received on the dynamic topic
+    pub fn dynamic_statistics(&self) -> TopicStatistics {
+        lock_statistics(&self.dynamic_statistics).clone()
+    }
+
+    /// Returns the statistics of the messages received on the static topic
+    pub fn static_statistics(&self) -> TopicStatistics {
+        lock_statistics(&self.static_statistics).clone()
+    }
+
+    /// Returns a snapshot of the buffer, which is not affected by transforms received later
+    pub fn buffer(&self) -> Arc<TfBuffer> {
+        self.state.buffer.snapshot()
+    }
+
+    /// Returns the buffer which stores the received transforms
+    pub fn shared_buffer(&self) -> Arc<SharedTfBuffer> {
+        self.state.buffer.clone()
     }
 
     /// Looks up a transform within the tree at a given time.
@@ -70,10 +235,58 @@ impl TfListener {
         to: &str,
         time: Time,
     ) -> Result<TransformStamped, TfError> {
-        self.buffer
-            .read()
-            .unwrap()
-            .lookup_transform(from, to, &time)
+        self.state.buffer.lookup_transform(from, to, &time)
+    }
+
+    /// Returns the clock of the listener
+    pub fn clock(&self) -> &TfClock {
+        &self.state.clock
+    }
+
+    /// Looks up a transform within the tree at the current time of the clock.
+    pub fn lookup_transform_at_now(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<TransformStamped, TfError> {
+        self.lookup_transform(from, to, self.state.clock.now()?)
+    }
+
+    /// Waits until the transform is available or the timeout elapses.
+    ///
+    /// The timeout is measured with the clock of the listener, so it follows the simulated time.
+    /// Returns the last lookup error if the timeout elapses, and [`TfError::Cancelled`] if the
+    /// listener is shut down while waiting.
+    ///
+    /// This must be awaited within a tokio runtime, also for listeners created by
+    /// [`TfListenerBuilder::build_polling`]. Those process the received messages while waiting,
+    /// but the node must still be spun meanwhile, e.g. by another thread calling
+    /// `r2r::Node::spin_once`.
+    pub async fn wait_for_transform(
+        &self,
+        from: &str,
+        to: &str,
+        time: Time,
+        timeout: Duration,
+    ) -> Result<TransformStamped, TfError> {
+        self.wait_for(timeout, || self.lookup_transform(from, to, time.clone()))
+            .await
+    }
+
+    /// Waits until `lookup` succeeds or the timeout elapses, retrying whenever transforms are
+    /// received
+    pub(crate) async fn wait_for(
+        &self,
+        timeout: Duration,
+        mut lookup: impl FnMut() -> Result<TransformStamped, TfError>,
+    ) -> Result<TransformStamped, TfError> {
+        self.state
+            .wait_for(timeout, || {
+                // Nothing else processes the messages of a polling listener while waiting.
+                self.poll();
+                lookup()
+            })
+            .await
     }
 
     /// Looks up a transform within the tree at a given time.
@@ -85,9 +298,818 @@ impl TfListener {
         time2: Time,
         fixed_frame: &str,
     ) -> Result<TransformStamped, TfError> {
-        self.buffer
-            .read()
-            .unwrap()
+        self.state
+            .buffer
+            .snapshot()
             .lookup_transform_with_time_travel(from, time1, to, time2, fixed_frame)
     }
+
+    /// Creates a listener of the buffer which does not subscribe to any topic
+    #[cfg(test)]
+    pub(crate) fn without_subscriptions(tf_buffer: TfBuffer) -> Self {
+        let state = Arc::new(ListenerState::new(
+            Arc::new(tf_buffer.into()),
+            TfClock::system(),
+        ));
+        TfListener {
+            state: state.clone(),
+            dynamic_statistics: Arc::default(),
+            static_statistics: Arc::default(),
+            subscriptions: Arc::new(Subscriptions::new(state)),
+        }
+    }
+
+    /// Stops processing the subscriptions and wakes up all tasks waiting for transforms.
+    ///
+    /// Once this returns, the buffer is no longer updated. The subscriber tasks end as soon as
+    /// they are scheduled again. This affects all clones of the listener, and is also called when
+    /// the last clone is dropped.
+    pub fn shutdown(&self) {
+        self.subscriptions.shutdown();
+    }
+
+    /// Returns `true` if [`TfListener::shutdown`] was called
+    pub fn is_shutdown(&self) -> bool {
+        self.state.is_shutdown.load(Ordering::Acquire)
+    }
+}
+
+impl Subscriptions {
+    fn new(state: Arc<ListenerState>) -> Self {
+        Self {
+            state,
+            pollers: Mutex::new(Vec::new()),
+            frame_graph: Mutex::new(None),
+            tasks: Mutex::new(Vec::new()),
+            shutdown: watch::channel(false).0,
+        }
+    }
+
+    fn shutdown(&self) {
+        self.state.shutdown();
+        self.shutdown.send_replace(true);
+        self.pollers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.frame_graph
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+impl Drop for Subscriptions {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl ListenerState {
+    fn new(buffer: Arc<SharedTfBuffer>, clock: TfClock) -> Self {
+        Self {
+            buffer,
+            is_shutdown: AtomicBool::new(false),
+            updated: Notify::new(),
+            clock,
+            last_update: Mutex::new(None),
+        }
+    }
+
+    /// Returns the length of the jump if the time moved backward by more than [`MIN_TIME_JUMP`]
+    /// since the last update
+    fn detect_time_jump(&self, now: Option<Duration>) -> Option<Duration> {
+        let now = now?;
+        let mut last_update = self
+            .last_update
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let jump = last_update.and_then(|last_update| last_update.checked_sub(now));
+        *last_update = Some(now);
+        jump.filter(|jump| *jump > MIN_TIME_JUMP)
+    }
+
+    fn shutdown(&self) {
+        {
+            let _update = self.buffer.lock_updates();
+            self.is_shutdown.store(true, Ordering::Release);
+        }
+        self.updated.notify_waiters();
+    }
+
+    async fn wait_for(
+        &self,
+        timeout: Duration,
+        mut lookup: impl FnMut() -> Result<TransformStamped, TfError>,
+    ) -> Result<TransformStamped, TfError> {
+        let deadline = self.clock.now_since_epoch()? + timeout;
+        loop {
+            // Registered before the lookup, so that no update is missed.
+            let notified = self.updated.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.is_shutdown.load(Ordering::Acquire) {
+                return Err(TfError::Cancelled);
+            }
+            let result = lookup();
+            let remaining = deadline.checked_sub(self.clock.now_since_epoch()?);
+            match remaining {
+                Some(remaining) if result.is_err() && !remaining.is_zero() => {
+                    // The clock may not run at the speed of the system time, so the deadline is
+                    // checked again regularly.
+                    let _ = tokio::time::timeout(remaining.min(CLOCK_CHECK_PERIOD), notified).await;
+                }
+                _ => return result,
+            }
+        }
+    }
+}
+
+/// Builder of [`TfListener`]
+#[derive(Debug)]
+#[must_use]
+pub struct TfListenerBuilder {
+    buffer: Option<Arc<SharedTfBuffer>>,
+    dynamic_topic: String,
+    static_topic: String,
+    dynamic_qos: QosProfile,
+    static_qos: QosProfile,
+    authority: String,
+    clock: Option<TfClock>,
+    frame_graph_service: Option<String>,
+}
+
+impl Default for TfListenerBuilder {
+    fn default() -> Self {
+        Self {
+            buffer: None,
+            dynamic_topic: DEFAULT_DYNAMIC_TOPIC.to_string(),
+            static_topic: DEFAULT_STATIC_TOPIC.to_string(),
+            dynamic_qos: dynamic_listener_qos(),
+            static_qos: static_listener_qos(),
+            authority: DEFAULT_AUTHORITY.to_string(),
+            clock: None,
+            frame_graph_service: None,
+        }
+    }
+}
+
+impl TfListenerBuilder {
+    /// Sets the buffer which stores the received transforms
+    pub fn buffer(self, tf_buffer: TfBuffer) -> Self {
+        self.shared_buffer(Arc::new(SharedTfBuffer::new(tf_buffer)))
+    }
+
+    /// Sets a buffer which may be shared with other listeners or components
+    pub fn shared_buffer(mut self, tf_buffer: Arc<SharedTfBuffer>) -> Self {
+        self.buffer = Some(tf_buffer);
+        self
+    }
+
+    /// Sets the topics of the dynamic and static transforms. The defaults are `/tf` and
+    /// `/tf_static`.
+    pub fn topics(
+        mut self,
+        dynamic_topic: impl Into<String>,
+        static_topic: impl Into<String>,
+    ) -> Self {
+        self.dynamic_topic = dynamic_topic.into();
+        self.static_topic = static_topic.into();
+        self
+    }
+
+    /// Subscribes to `tf` and `tf_static` in the given namespace
+    pub fn namespace(self, namespace: &str) -> Self {
+        let (dynamic_topic, static_topic) = namespaced_topics(namespace);
+        self.topics(dynamic_topic, static_topic)
+    }
+
+    /// Sets the history depth of the dynamic subscriber. The default is 100.
+    ///
+    /// The static subscriber keeps all transforms.
+    pub fn queue_size(mut self, queue_size: usize) -> Self {
+        self.dynamic_qos = self.dynamic_qos.keep_last(queue_size);
+        self
+    }
+
+    /// Sets the QoS of the dynamic subscriber. The default is [`dynamic_listener_qos`].
+    pub fn dynamic_qos(mut self, qos: QosProfile) -> Self {
+        self.dynamic_qos = qos;
+        self
+    }
+
+    /// Sets the QoS of the static subscriber. The default is [`static_listener_qos`].
+    ///
+    /// The durability should be transient local to receive the transforms which were published
+    /// before the listener was created.
+    pub fn static_qos(mut self, qos: QosProfile) -> Self {
+        self.static_qos = qos;
+        self
+    }
+
+    /// Sets the authority recorded for the received transforms. The default is
+    /// `"Authority undetectable"`, like in tf2_ros, as r2r does not tell which node published a
+    /// message.
+    pub fn authority(mut self, authority: impl Into<String>) -> Self {
+        self.authority = authority.into();
+        self
+    }
+
+    /// Sets the clock of the listener. The default is the ROS clock of the node, see
+    /// [`TfClock::ros`].
+    pub fn clock(mut self, clock: TfClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Creates a `tf2_msgs/FrameGraph` service with the given name, which answers the frames of
+    /// the buffer in YAML. tf2_ros uses `tf2_frames`. By default, no service is created.
+    pub fn frame_graph_service(mut self, service: impl Into<String>) -> Self {
+        self.frame_graph_service = Some(service.into());
+        self
+    }
+
+    /// Creates the TfListener and subscribes to the topics
+    ///
+    /// The messages are processed by tasks spawned on the current tokio runtime. Returns an error
+    /// if this is called outside of a tokio runtime.
+    pub fn build(self, node: &mut r2r::Node) -> Result<TfListener, TfError> {
+        let runtime =
+            tokio::runtime::Handle::try_current().map_err(|err| TfError::R2r(err.to_string()))?;
+        let (listener, subscribers, frame_graph) = self.subscribe(node)?;
+        let mut tasks: Vec<_> = subscribers
+            .into_iter()
+            .map(|(subscriber, ingestor)| {
+                runtime.spawn(ingest_transforms(
+                    subscriber,
+                    ingestor,
+                    listener.subscriptions.shutdown.subscribe(),
+                ))
+            })
+            .collect();
+        if let Some(requests) = frame_graph {
+            tasks.push(runtime.spawn(serve_frame_graph(
+                requests,
+                listener.state.clone(),
+                listener.subscriptions.shutdown.subscribe(),
+            )));
+        }
+        *listener
+            .subscriptions
+            .tasks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = tasks;
+        Ok(listener)
+    }
+
+    /// Creates the TfListener and subscribes to the topics, without spawning any task
+    ///
+    /// This does not need a tokio runtime. The received messages are processed only when
+    /// [`TfListener::poll`] or [`TfListener::spin_once`] is called, or while
+    /// [`TfListener::wait_for_transform`] waits.
+    pub fn build_polling(self, node: &mut r2r::Node) -> Result<TfListener, TfError> {
+        let (listener, subscribers, frame_graph) = self.subscribe(node)?;
+        *listener
+            .subscriptions
+            .frame_graph
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = frame_graph;
+        *listener
+            .subscriptions
+            .pollers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = subscribers
+            .into_iter()
+            .map(|(subscriber, ingestor)| Poller {
+                subscriber,
+                ingestor,
+            })
+            .collect();
+        Ok(listener)
+    }
+
+    fn subscribe(self, node: &mut r2r::Node) -> Result<Subscribed, TfError> {
+        let dynamic_subscriber = node
+            .subscribe::<TFMessage>(&self.dynamic_topic, self.dynamic_qos)
+            .map_err(|err| TfError::R2r(err.to_string()))?;
+        let static_subscriber = node
+            .subscribe::<TFMessage>(&self.static_topic, self.static_qos)
+            .map_err(|err| TfError::R2r(err.to_string()))?;
+        let frame_graph = self
+            .frame_graph_service
+            .map(|name| {
+                node.create_service::<FrameGraph::Service>(&name, QosProfile::services_default())
+            })
+            .transpose()
+            .map_err(|err| TfError::R2r(err.to_string()))?
+            .map(|requests| Box::pin(requests) as FrameGraphStream);
+
+        let clock = self.clock.unwrap_or_else(|| TfClock::ros(node));
+        let state = Arc::new(ListenerState::new(self.buffer.unwrap_or_default(), clock));
+        let dynamic_statistics = Arc::new(Mutex::new(TopicStatistics::default()));
+        let static_statistics = Arc::new(Mutex::new(TopicStatistics::default()));
+        let authority: Arc<str> = self.authority.into();
+        let subscribers: Vec<(TfMessageStream, _)> = vec![
+            (
+                Box::pin(dynamic_subscriber),
+                Ingestor {
+                    state: state.clone(),
+                    statistics: dynamic_statistics.clone(),
+                    authority: authority.clone(),
+                    static_tf: false,
+                },
+            ),
+            (
+                Box::pin(static_subscriber),
+                Ingestor {
+                    state: state.clone(),
+                    statistics: static_statistics.clone(),
+                    authority,
+                    static_tf: true,
+                },
+            ),
+        ];
+
+        let listener = TfListener {
+            state: state.clone(),
+            dynamic_statistics,
+            static_statistics,
+            subscriptions: Arc::new(Subscriptions::new(state)),
+        };
+        Ok((listener, subscribers, frame_graph))
+    }
+}
+
+type TfMessageStream = Pin<Box<dyn Stream<Item = TFMessage> + Send>>;
+/// The listener, its subscribers and its `FrameGraph` service
+type Subscribed = (
+    TfListener,
+    Vec<(TfMessageStream, Ingestor)>,
+    Option<FrameGraphStream>,
+);
+type FrameGraphStream = Pin<Box<dyn Stream<Item = ServiceRequest<FrameGraph::Service>> + Send>>;
+
+struct Poller {
+    subscriber: TfMessageStream,
+    ingestor: Ingestor,
+}
+
+/// Inserts the received messages into the buffer and records their statistics
+struct Ingestor {
+    state: Arc<ListenerState>,
+    statistics: Arc<Mutex<TopicStatistics>>,
+    authority: Arc<str>,
+    static_tf: bool,
+}
+
+impl Ingestor {
+    fn ingest(&self, msg: TFMessage) {
+        let inserted = self.state.buffer.update(|buffer| {
+            // Checked while holding the update lock, so that no message is inserted after
+            // `shutdown` returned.
+            if self.state.is_shutdown.load(Ordering::Acquire) {
+                return None;
+            }
+            // Read while holding the update lock, so that the updates see increasing times.
+            let now = self.state.clock.now_since_epoch().ok();
+            if let Some(jump) = self.state.detect_time_jump(now) {
+                r2r::log_warn!(
+                    "tf_r2r",
+                    "detected jump back in time of {}s, clearing the buffer",
+                    jump.as_secs_f64()
+                );
+                buffer.clear();
+            }
+            let lag = now.and_then(|now| message_lag(&msg, now));
+            let late = !self.static_tf
+                && lag.is_some_and(|lag| lag.as_nanos() > buffer.cache_duration_nanos() as u128);
+            let dropped = buffer.handle_incoming_transforms(msg, &self.authority, self.static_tf);
+            Some((lag, late, dropped))
+        });
+        if let Some((lag, late, dropped)) = inserted {
+            lock_statistics(&self.statistics).record(lag, late, dropped);
+            self.state.updated.notify_waiters();
+        }
+    }
+}
+
+/// Inserts the messages into the buffer as soon as they arrive, until the stream ends or the
+/// listener is shut down.
+async fn ingest_transforms(
+    mut subscriber: TfMessageStream,
+    ingestor: Ingestor,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        let msg = tokio::select! {
+            _ = shutdown.changed() => break,
+            msg = subscriber.next() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+        };
+        ingestor.ingest(msg);
+    }
+}
+
+/// Answers the `FrameGraph` requests as soon as they arrive, until the stream ends or the listener
+/// is shut down.
+async fn serve_frame_graph(
+    mut requests: FrameGraphStream,
+    state: Arc<ListenerState>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        let request = tokio::select! {
+            _ = shutdown.changed() => break,
+            request = requests.next() => match request {
+                Some(request) => request,
+                None => break,
+            },
+        };
+        answer_frame_graph(&state, request);
+    }
+}
+
+fn answer_frame_graph(state: &ListenerState, request: ServiceRequest<FrameGraph::Service>) {
+    let response = FrameGraph::Response {
+        frame_yaml: state.buffer.snapshot().all_frames_as_yaml(),
+    };
+    if let Err(err) = request.respond(response) {
+        r2r::log_warn!("tf_r2r", "failed to answer a FrameGraph request: {err}");
+    }
+}
+
+/// Returns the difference between `now`, the time of the clock since the epoch, and the newest
+/// stamp of the message
+fn message_lag(msg: &TFMessage, now: Duration) -> Option<Duration> {
+    let newest = msg
+        .transforms
+        .iter()
+        .map(|tf| time_as_ns_i64(&tf.header.stamp))
+        .max()?;
+    let now = now.as_nanos() as i64;
+    Some(Duration::from_nanos(
+        now.saturating_sub(newest).max(0) as u64
+    ))
+}
+
+fn lock_statistics(
+    statistics: &Mutex<TopicStatistics>,
+) -> std::sync::MutexGuard<'_, TopicStatistics> {
+    statistics.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl TopicStatistics {
+    fn record(&mut self, lag: Option<Duration>, late: bool, dropped: usize) {
+        self.received += 1;
+        if late {
+            self.late += 1;
+        }
+        self.dropped += dropped as u64;
+        if lag.is_some() {
+            self.last_lag = lag;
+            self.max_lag = self.max_lag.max(lag);
+        }
+    }
+}
+
+const DEFAULT_DYNAMIC_TOPIC: &str = "/tf";
+const DEFAULT_STATIC_TOPIC: &str = "/tf_static";
+const DEFAULT_AUTHORITY: &str = "Authority undetectable";
+const CLOCK_CHECK_PERIOD: Duration = Duration::from_millis(100);
+/// Smaller backward steps of the time are not treated as jumps, as they are caused by callbacks
+/// reading the time concurrently or by clock adjustments
+const MIN_TIME_JUMP: Duration = Duration::from_millis(100);
+
+/// Returns the `tf` and `tf_static` topics in the given namespace
+pub(crate) fn namespaced_topics(namespace: &str) -> (String, String) {
+    let namespace = namespace.trim_end_matches('/');
+    (format!("{namespace}/tf"), format!("{namespace}/tf_static"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::*;
+    use crate::{test_utils::transform, utils::time_from_nanosec};
+
+    fn message(stamp: Time) -> TFMessage {
+        let mut tf = transform("odom", "base_link", 0, 0.0);
+        tf.header.stamp = stamp;
+        TFMessage {
+            transforms: vec![tf],
+        }
+    }
+
+    fn now() -> Time {
+        time_from_nanosec(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos() as i64,
+        )
+    }
+
+    fn ingestor(state: &Arc<ListenerState>) -> Ingestor {
+        Ingestor {
+            state: state.clone(),
+            statistics: Arc::new(Mutex::new(TopicStatistics::default())),
+            authority: "publisher".into(),
+            static_tf: false,
+        }
+    }
+
+    fn listener(state: Arc<ListenerState>) -> TfListener {
+        TfListener {
+            state: state.clone(),
+            dynamic_statistics: Arc::default(),
+            static_statistics: Arc::default(),
+            subscriptions: Arc::new(Subscriptions::new(state)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ingest_transforms() {
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        let state = Arc::new(ListenerState::new(Arc::default(), TfClock::system()));
+        let ingestor = ingestor(&state);
+        let statistics = ingestor.statistics.clone();
+        let (shutdown, shutdown_receiver) = watch::channel(false);
+        let task = tokio::spawn(ingest_transforms(
+            Box::pin(receiver),
+            ingestor,
+            shutdown_receiver,
+        ));
+
+        for _ in 0..100 {
+            sender.unbounded_send(message(now())).unwrap();
+        }
+        // Older than the cache duration, which is reported and then dropped by the buffer
+        sender
+            .unbounded_send(message(Time { sec: 1, nanosec: 0 }))
+            .unwrap();
+        // Rejected by the buffer
+        let mut invalid = message(now());
+        invalid.transforms[0].child_frame_id.clear();
+        sender.unbounded_send(invalid).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while lock_statistics(&statistics).received < 102 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let statistics = lock_statistics(&statistics).clone();
+        assert_eq!(statistics.late, 1);
+        assert_eq!(statistics.dropped, 2);
+        assert!(statistics.max_lag.unwrap() >= statistics.last_lag.unwrap());
+        assert!(state
+            .buffer
+            .lookup_transform("odom", "base_link", &Time::default())
+            .is_ok());
+        // The late transform was not stored.
+        assert!(state
+            .buffer
+            .lookup_transform("odom", "base_link", &Time { sec: 1, nanosec: 0 })
+            .is_err());
+        assert_eq!(
+            state.buffer.snapshot().authority("odom", "base_link"),
+            Some("publisher")
+        );
+
+        drop(shutdown);
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_message_lag() {
+        // Stamps of a simulation are compared with the simulated time, not the system time.
+        let msg = message(Time {
+            sec: 100,
+            nanosec: 0,
+        });
+        assert_eq!(
+            message_lag(&msg, Duration::from_millis(100_500)),
+            Some(Duration::from_millis(500))
+        );
+        // Stamps ahead of the clock have no lag.
+        assert_eq!(
+            message_lag(&msg, Duration::from_secs(99)),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            message_lag(&TFMessage::default(), Duration::from_secs(99)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_poll() {
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        let state = Arc::new(ListenerState::new(Arc::default(), TfClock::system()));
+        let listener = listener(state.clone());
+        listener.subscriptions.pollers.lock().unwrap().push(Poller {
+            subscriber: Box::pin(receiver),
+            ingestor: ingestor(&state),
+        });
+        assert_eq!(listener.poll(), 0);
+        sender.unbounded_send(message(now())).unwrap();
+        sender.unbounded_send(message(now())).unwrap();
+        assert_eq!(listener.poll(), 2);
+        assert!(listener
+            .lookup_transform("odom", "base_link", Time::default())
+            .is_ok());
+
+        drop(sender);
+        assert_eq!(listener.poll(), 0);
+        assert!(listener.subscriptions.pollers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lookup_transform_at_now() {
+        let state = Arc::new(ListenerState::new(Arc::default(), TfClock::system()));
+        let listener = listener(state.clone());
+        assert!(listener
+            .lookup_transform_at_now("odom", "base_link")
+            .is_err());
+        let tf = message(now()).transforms.remove(0);
+        state
+            .buffer
+            .update(|buffer| buffer.set_transform(&tf, "publisher", true))
+            .unwrap();
+        let result = listener.lookup_transform_at_now("odom", "base_link");
+        assert!(time_as_ns_i64(&result.unwrap().header.stamp) >= time_as_ns_i64(&tf.header.stamp));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_transform_polling() {
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        let state = Arc::new(ListenerState::new(Arc::default(), TfClock::system()));
+        let listener = listener(state.clone());
+        listener.subscriptions.pollers.lock().unwrap().push(Poller {
+            subscriber: Box::pin(receiver),
+            ingestor: ingestor(&state),
+        });
+        let waiter = {
+            let listener = listener.clone();
+            tokio::spawn(async move {
+                listener
+                    .wait_for_transform(
+                        "odom",
+                        "base_link",
+                        Time::default(),
+                        Duration::from_secs(10),
+                    )
+                    .await
+            })
+        };
+        // Received while waiting, as if another thread spun the node, and processed without
+        // calling `poll`.
+        let start = std::time::Instant::now();
+        sender.unbounded_send(message(now())).unwrap();
+        assert!(waiter.await.unwrap().is_ok());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_transform() {
+        let state = Arc::new(ListenerState::new(Arc::default(), TfClock::system()));
+        let listener = listener(state.clone());
+        let result = listener
+            .wait_for_transform(
+                "odom",
+                "base_link",
+                Time::default(),
+                Duration::from_millis(10),
+            )
+            .await;
+        assert!(matches!(result, Err(TfError::CouldNotFindTransform(..))));
+
+        let waiter = {
+            let listener = listener.clone();
+            tokio::spawn(async move {
+                listener
+                    .wait_for_transform(
+                        "odom",
+                        "base_link",
+                        Time::default(),
+                        Duration::from_secs(10),
+                    )
+                    .await
+            })
+        };
+        tokio::task::yield_now().await;
+        ingestor(&state).ingest(message(now()));
+        assert!(waiter.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        let state = Arc::new(ListenerState::new(Arc::default(), TfClock::system()));
+        let listener = listener(state.clone());
+        listener
+            .subscriptions
+            .tasks
+            .lock()
+            .unwrap()
+            .push(tokio::spawn(ingest_transforms(
+                Box::pin(receiver),
+                ingestor(&state),
+                listener.subscriptions.shutdown.subscribe(),
+            )));
+        let waiter = {
+            let listener = listener.clone();
+            tokio::spawn(async move {
+                listener
+                    .wait_for_transform(
+                        "odom",
+                        "base_link",
+                        Time::default(),
+                        Duration::from_secs(10),
+                    )
+                    .await
+            })
+        };
+        tokio::task::yield_now().await;
+
+        listener.shutdown();
+        assert!(listener.is_shutdown());
+        let result = tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(result, Err(TfError::Cancelled)));
+        let tasks = std::mem::take(&mut *listener.subscriptions.tasks.lock().unwrap());
+        for task in tasks {
+            tokio::time::timeout(Duration::from_secs(1), task)
+                .await
+                .unwrap()
+                .unwrap();
+        }
+
+        // Messages which arrive after the shutdown do not touch the buffer.
+        assert!(sender.unbounded_send(message(now())).is_err());
+        ingestor(&state).ingest(message(now()));
+        assert!(listener
+            .lookup_transform("odom", "base_link", Time::default())
+            .is_err());
+    }
+
+    #[test]
+    fn test_detect_time_jump() {
+        let state = ListenerState::new(Arc::default(), TfClock::system());
+        let time = Duration::from_secs;
+        assert_eq!(state.detect_time_jump(Some(time(10))), None);
+        assert_eq!(state.detect_time_jump(Some(time(10))), None);
+        assert_eq!(state.detect_time_jump(Some(time(11))), None);
+        assert_eq!(state.detect_time_jump(None), None);
+        assert_eq!(state.detect_time_jump(Some(time(1))), Some(time(10)));
+        assert_eq!(state.detect_time_jump(Some(time(2))), None);
+        // Small backward steps are ignored.
+        let small_step = time(2) - Duration::from_millis(50);
+        assert_eq!(state.detect_time_jump(Some(small_step)), None);
+    }
+
+    #[test]
+    fn test_clone_shares_buffer() {
+        let buffer = Arc::new(SharedTfBuffer::default());
+        let state = Arc::new(ListenerState::new(buffer.clone(), TfClock::system()));
+        let listener = listener(state.clone());
+        let other = listener.clone();
+        ingestor(&state).ingest(message(now()));
+        assert!(other
+            .buffer()
+            .lookup_transform("odom", "base_link", &Time::default())
+            .is_ok());
+        assert!(Arc::ptr_eq(&other.shared_buffer(), &buffer));
+
+        // Dropping a clone keeps the listener running.
+        drop(other);
+        assert!(!listener.is_shutdown());
+        drop(listener);
+        assert!(state.is_shutdown.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_namespaced_topics() {
+        assert_eq!(
+            namespaced_topics("/robot1"),
+            ("/robot1/tf".to_string(), "/robot1/tf_static".to_string())
+        );
+        assert_eq!(
+            namespaced_topics("robot1/"),
+            ("robot1/tf".to_string(), "robot1/tf_static".to_string())
+        );
+        assert_eq!(
+            namespaced_topics("/"),
+            ("/tf".to_string(), "/tf_static".to_string())
+        );
+    }
 }
@@ -0,0 +1,208 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use r2r::{builtin_interfaces::msg::Time, geometry_msgs::msg::TransformStamped};
+
+use crate::{tf_broadcaster::TfBroadcaster, tf_error::TfError};
+
+/// Broadcast a set of transforms at a fixed rate from a background task
+///
+/// Every transform is restamped with the node's ROS clock right before it is sent. The set of
+/// transforms can be updated at any time, either directly or through a
+/// [`PeriodicTfBroadcasterHandle`]. The background task is stopped when the broadcaster is
+/// dropped.
+///
+/// This must be created from within a tokio runtime.
+pub struct PeriodicTfBroadcaster {
+    handle: PeriodicTfBroadcasterHandle,
+    task: tokio::task::JoinHandle<()>,
+}
+
+/// A cheaply cloneable handle to update the transforms of a [`PeriodicTfBroadcaster`]
+#[derive(Clone, Debug, Default)]
+pub struct PeriodicTfBroadcasterHandle {
+    transforms: Arc<Mutex<BTreeMap<String, TransformStamped>>>,
+}
+
+impl PeriodicTfBroadcaster {
+    /// Create a new PeriodicTfBroadcaster publishing at `rate` Hz
+    pub fn new(node: &mut r2r::Node, rate: f64) -> Result<Self, TfError> {
+        let broadcaster = TfBroadcaster::try_new(node)?;
+        Self::new_with_broadcaster(node, broadcaster, rate)
+    }
+
+    /// Create a new PeriodicTfBroadcaster publishing through the given broadcaster at `rate` Hz
+    pub fn new_with_broadcaster(
+        node: &mut r2r::Node,
+        broadcaster: TfBroadcaster,
+        rate: f64,
+    ) -> Result<Self, TfError> {
+        let period = period(rate)?;
+        let runtime =
+            tokio::runtime::Handle::try_current().map_err(|err| TfError::R2r(err.to_string()))?;
+        let clock = node.get_ros_clock();
+        let handle = PeriodicTfBroadcasterHandle::default();
+
+        let task_handle = handle.clone();
+        let task = runtime.spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            // The first tick completes immediately.
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let now = clock
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .get_now()
+                    .map(|now| r2r::Clock::to_builtin_time(&now));
+                let result = match now {
+                    Ok(now) => broadcaster.send_transforms(task_handle.stamped_transforms(now)),
+                    Err(err) => Err(TfError::R2r(err.to_string())),
+                };
+                if let Err(err) = result {
+                    r2r::log_warn!("tf_r2r", "failed to broadcast transforms: {err}");
+                }
+            }
+        });
+
+        Ok(Self { handle, task })
+    }
+
+    /// Returns a handle which can update the broadcast transforms from other tasks
+    pub fn handle(&self) -> PeriodicTfBroadcasterHandle {
+        self.handle.clone()
+    }
+
+    /// Adds the transform to the broadcast set, replacing the one with the same child frame
+    pub fn set_transform(&self, tf: TransformStamped) {
+        self.handle.set_transform(tf);
+    }
+
+    /// Removes the transform of `child_frame_id` from the broadcast set
+    pub fn remove_transform(&self, child_frame_id: &str) -> Option<TransformStamped> {
+        self.handle.remove_transform(child_frame_id)
+    }
+}
+
+impl Drop for PeriodicTfBroadcaster {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl PeriodicTfBroadcasterHandle {
+    /// Adds the transform to the broadcast set, replacing the one with the same child frame
+    pub fn set_transform(&self, tf: TransformStamped) {
+        self.lock().insert(tf.child_frame_id.clone(), tf);
+    }
+
+    /// Adds all transforms to the broadcast set
+    pub fn set_transforms(&self, transforms: impl IntoIterator<Item = TransformStamped>) {
+        let mut current = self.lock();
+        for tf in transforms {
+            current.insert(tf.child_frame_id.clone(), tf);
+        }
+    }
+
+    /// Removes the transform of `child_frame_id` from the broadcast set
+    pub fn remove_transform(&self, child_frame_id: &str) -> Option<TransformStamped> {
+        self.lock().remove(child_frame_id)
+    }
+
+    /// Removes all transforms from the broadcast set
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Returns the transforms currently in the broadcast set
+    pub fn transforms(&self) -> Vec<TransformStamped> {
+        self.lock().values().cloned().collect()
+    }
+
+    fn stamped_transforms(&self, stamp: Time) -> Vec<TransformStamped> {
+        self.lock()
+            .values()
+            .map(|tf| {
+                let mut tf = tf.clone();
+                tf.header.stamp = stamp.clone();
+                tf
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, TransformStamped>> {
+        // The map is always left in a valid state, so it is fine to ignore poisoning.
+        self.transforms
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Returns the period of the rate, which must be positive and have a non-zero period
+fn period(rate: f64) -> Result<Duration, TfError> {
+    Duration::try_from_secs_f64(1.0 / rate)
+        .ok()
+        .filter(|period| !period.is_zero())
+        .ok_or_else(|| {
+            TfError::InvalidArgument(format!(
+                "rate must be a positive number with a non-zero period, but got {rate}"
+            ))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::transform;
+
+    #[test]
+    fn test_handle_updates() {
+        let handle = PeriodicTfBroadcasterHandle::default();
+        let other = handle.clone();
+        handle.set_transform(transform("odom", "base_link", 0, 1.0));
+        other.set_transforms([
+            transform("odom", "base_link", 0, 2.0),
+            transform("base_link", "camera", 0, 0.5),
+        ]);
+        let transforms = handle.transforms();
+        assert_eq!(transforms.len(), 2);
+        assert_eq!(transforms[0].child_frame_id, "base_link");
+        assert_eq!(transforms[0].transform.translation.x, 2.0);
+
+        assert!(other.remove_transform("camera").is_some());
+        assert!(other.remove_transform("camera").is_none());
+        assert_eq!(handle.transforms().len(), 1);
+
+        handle.clear();
+        assert!(other.transforms().is_empty());
+    }
+
+    #[test]
+    fn test_restamp() {
+        let handle = PeriodicTfBroadcasterHandle::default();
+        handle.set_transform(transform("odom", "base_link", 0, 1.0));
+        let stamp = Time {
+            sec: 5,
+            nanosec: 10,
+        };
+        let transforms = handle.stamped_transforms(stamp.clone());
+        assert_eq!(transforms[0].header.stamp, stamp);
+        // The stored transform keeps its original stamp.
+        assert_eq!(handle.transforms()[0].header.stamp, Time::default());
+    }
+
+    #[test]
+    fn test_period() {
+        assert_eq!(period(10.0).unwrap(), Duration::from_millis(100));
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(period(rate), Err(TfError::InvalidArgument(_))));
+        }
+        // The period of tiny rates overflows, and the one of huge rates rounds to zero.
+        assert!(matches!(period(1e-310), Err(TfError::InvalidArgument(_))));
+        assert!(matches!(period(1e300), Err(TfError::InvalidArgument(_))));
+    }
+}
@@ -0,0 +1,46 @@
+/// How a [`TfBuffer`](crate::TfBuffer) handles repeated and late dynamic transforms
+///
+/// Static transforms always replace a stored transform with the same stamp, and are never late.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SamplePolicy {
+    /// How transforms with the same stamp as a stored transform of the same edge are handled
+    pub duplicate: DuplicatePolicy,
+    /// How transforms older than the cache duration, compared to the newest transform of the same
+    /// edge, are handled
+    pub late: LatePolicy,
+}
+
+/// How a [`SamplePolicy`] handles transforms with the same stamp as a stored one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Replace the stored transform
+    Replace,
+    /// Keep the stored transform, like tf2 does
+    #[default]
+    Ignore,
+    /// Reject the transform with [`TfError::RepeatedData`](crate::TfError::RepeatedData)
+    Error,
+}
+
+/// How a [`SamplePolicy`] handles transforms older than the cache duration
+///
+/// These transforms would be removed right after being inserted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LatePolicy {
+    /// Drop the transform, like tf2 does
+    #[default]
+    Ignore,
+    /// Reject the transform with [`TfError::OldData`](crate::TfError::OldData)
+    Error,
+}
+
+/// Counters of the transforms received for an edge of a [`TfBuffer`](crate::TfBuffer)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SampleStatistics {
+    /// Number of received transforms, including the duplicate and late ones
+    pub received: u64,
+    /// Number of transforms with the same stamp as a stored one
+    pub duplicates: u64,
+    /// Number of transforms older than the cache duration
+    pub late: u64,
+}
@@ -0,0 +1,241 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, MutexGuard, PoisonError, RwLock, TryLockError,
+};
+
+use r2r::{builtin_interfaces::msg::Time, geometry_msgs::msg::TransformStamped};
+
+use crate::{tf_buffer::TfBuffer, tf_error::TfError};
+
+/// A [`TfBuffer`] which can be read and updated from several threads at once
+///
+/// Readers take a snapshot of the buffer, which is never modified afterwards, so lookups never
+/// wait for incoming transforms to be inserted. Updates modify a working buffer in place, and a
+/// new snapshot of it is only taken when the buffer is read after an update, so a burst of
+/// incoming transforms costs a single snapshot. The snapshots share the stored transforms with the
+/// working buffer, which only copies the few transforms it modifies afterwards instead of the
+/// whole history.
+///
+/// Poisoning is ignored: a panic during an update keeps the changes made before it.
+#[derive(Debug)]
+pub struct SharedTfBuffer {
+    snapshot: RwLock<Arc<TfBuffer>>,
+    // Modified by the updates. The lock serializes them, so that none of them is lost.
+    buffer: Mutex<TfBuffer>,
+    // Whether the buffer was modified since the snapshot was taken
+    modified: AtomicBool,
+    // Whether a reader found the buffer locked, in which case the update takes the snapshot
+    requested: AtomicBool,
+}
+
+impl SharedTfBuffer {
+    /// Create a new SharedTfBuffer containing the given buffer
+    pub fn new(buffer: TfBuffer) -> Self {
+        Self {
+            snapshot: RwLock::new(Arc::new(buffer.clone())),
+            buffer: Mutex::new(buffer),
+            modified: AtomicBool::new(false),
+            requested: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the current state of the buffer
+    ///
+    /// The snapshot is not affected by later updates. If an update is being applied meanwhile,
+    /// this does not wait for it and returns the latest snapshot, which may miss the updates
+    /// applied right before. The update then takes a new snapshot when it returns.
+    pub fn snapshot(&self) -> Arc<TfBuffer> {
+        if self.modified.load(Ordering::Acquire) {
+            match self.buffer.try_lock() {
+                Ok(buffer) => self.publish(&buffer),
+                Err(TryLockError::Poisoned(err)) => self.publish(&err.into_inner()),
+                Err(TryLockError::WouldBlock) => self.requested.store(true, Ordering::Release),
+            }
+        }
+        self.snapshot
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Modifies the buffer, and returns the result of `f`
+    ///
+    /// Waits for the other updates to be applied first. Concurrent lookups keep using the
+    /// previous snapshot.
+    pub fn update<R>(&self, f: impl FnOnce(&mut TfBuffer) -> R) -> R {
+        self.apply(&mut self.lock_updates(), f)
+    }
+
+    /// Modifies the buffer like [`Self::update`], unless another update is being applied
+    ///
+    /// Returns [`TfError::CouldNotAcquireLock`] instead of waiting for the other update.
+    pub fn try_update<R>(&self, f: impl FnOnce(&mut TfBuffer) -> R) -> Result<R, TfError> {
+        let mut buffer = match self.buffer.try_lock() {
+            Ok(buffer) => buffer,
+            Err(TryLockError::Poisoned(err)) => err.into_inner(),
+            Err(TryLockError::WouldBlock) => return Err(TfError::CouldNotAcquireLock),
+        };
+        Ok(self.apply(&mut buffer, f))
+    }
+
+    /// Looks up a transform within the tree at a given time.
+    pub fn lookup_transform(
+        &self,
+        from: &str,
+        to: &str,
+        time: &Time,
+    ) -> Result<TransformStamped, TfError> {
+        self.snapshot().lookup_transform(from, to, time)
+    }
+
+    /// Locks out updates until the guard is dropped
+    pub(crate) fn lock_updates(&self) -> MutexGuard<'_, TfBuffer> {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Applies `f` to the locked buffer
+    fn apply<R>(&self, buffer: &mut TfBuffer, f: impl FnOnce(&mut TfBuffer) -> R) -> R {
+        // Set first, so that the changes made before a panic are not lost.
+        self.modified.store(true, Ordering::Release);
+        let result = f(buffer);
+        if self.requested.swap(false, Ordering::AcqRel) {
+            self.publish(buffer);
+        }
+        result
+    }
+
+    /// Replaces the snapshot if the locked buffer was modified since it was taken
+    fn publish(&self, buffer: &TfBuffer) {
+        if self.modified.swap(false, Ordering::AcqRel) {
+            *self
+                .snapshot
+                .write()
+                .unwrap_or_else(PoisonError::into_inner) = Arc::new(buffer.clone());
+        }
+    }
+}
+
+impl Default for SharedTfBuffer {
+    fn default() -> Self {
+        Self::new(TfBuffer::new())
+    }
+}
+
+impl From<TfBuffer> for SharedTfBuffer {
+    fn from(buffer: TfBuffer) -> Self {
+        Self::new(buffer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use r2r::tf2_msgs::msg::TFMessage;
+
+    use super::*;
+    use crate::test_utils::transform;
+
+    fn insert(buffer: &SharedTfBuffer, parent: &str, child: &str, x: f64) {
+        buffer.update(|buffer| {
+            buffer.handle_incoming_transforms(message(parent, child, x), "authority", true)
+        });
+    }
+
+    fn message(parent: &str, child: &str, x: f64) -> TFMessage {
+        TFMessage {
+            transforms: vec![transform(parent, child, x as i32, x)],
+        }
+    }
+
+    fn lookup_x(buffer: &TfBuffer) -> Result<f64, TfError> {
+        buffer
+            .lookup_transform("odom", "base_link", &Time::default())
+            .map(|tf| tf.transform.translation.x)
+    }
+
+    #[test]
+    fn test_snapshot_isolation() {
+        let buffer = SharedTfBuffer::default();
+        insert(&buffer, "odom", "base_link", 1.0);
+        let snapshot = buffer.snapshot();
+        insert(&buffer, "odom", "base_link", 2.0);
+        assert_eq!(lookup_x(&snapshot).unwrap(), 1.0);
+        assert_eq!(lookup_x(&buffer.snapshot()).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn test_panic_in_update() {
+        let buffer = SharedTfBuffer::default();
+        insert(&buffer, "odom", "base_link", 1.0);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            buffer.update(|buffer| {
+                buffer.handle_incoming_transforms(
+                    message("odom", "base_link", 2.0),
+                    "authority",
+                    true,
+                );
+                panic!("failed update");
+            })
+        }));
+        assert!(result.is_err());
+        // The changes made before the panic are kept and the buffer is still usable.
+        assert_eq!(lookup_x(&buffer.snapshot()).unwrap(), 2.0);
+        insert(&buffer, "odom", "base_link", 3.0);
+        assert_eq!(lookup_x(&buffer.snapshot()).unwrap(), 3.0);
+    }
+
+    #[test]
+    fn test_update_in_progress() {
+        let buffer = SharedTfBuffer::default();
+        insert(&buffer, "odom", "base_link", 1.0);
+        assert_eq!(lookup_x(&buffer.snapshot()).unwrap(), 1.0);
+        buffer.update(|tf_buffer| {
+            tf_buffer.handle_incoming_transforms(
+                message("odom", "base_link", 2.0),
+                "authority",
+                true,
+            );
+            // Lookups do not wait for the update, and see the previous snapshot.
+            assert_eq!(lookup_x(&buffer.snapshot()).unwrap(), 1.0);
+            assert!(matches!(
+                buffer.try_update(|_| ()),
+                Err(TfError::CouldNotAcquireLock)
+            ));
+        });
+        // The update took the snapshot which was requested meanwhile.
+        assert!(!buffer.modified.load(Ordering::Acquire));
+        assert_eq!(lookup_x(&buffer.snapshot()).unwrap(), 2.0);
+        assert!(buffer.try_update(|_| ()).is_ok());
+    }
+
+    #[test]
+    fn test_concurrent_updates() {
+        let buffer = Arc::new(SharedTfBuffer::default());
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let buffer = buffer.clone();
+                std::thread::spawn(move || {
+                    for j in 0..50 {
+                        let child = format!("link_{i}_{j}");
+                        insert(&buffer, "odom", &child, 1.0);
+                        // The update may not be in the snapshot yet if another one is being
+                        // applied.
+                        let _ = buffer.lookup_transform("odom", &child, &Time::default());
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let snapshot = buffer.snapshot();
+        for i in 0..4 {
+            for j in 0..50 {
+                assert!(snapshot
+                    .lookup_transform("odom", &format!("link_{i}_{j}"), &Time::default())
+                    .is_ok());
+            }
+        }
+    }
+}
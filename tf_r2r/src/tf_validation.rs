@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+
+use r2r::geometry_msgs::msg::TransformStamped;
+
+use crate::tf_error::TfError;
+
+/// Largest accepted difference between the squared norm of a rotation quaternion and 1, as in tf2
+const QUATERNION_TOLERANCE: f64 = 0.01;
+
+/// How a [`TfBuffer`](crate::TfBuffer) handles invalid transforms
+///
+/// A transform is invalid if a frame ID is empty, its parent and child frame are the same, it
+/// contains a NaN or infinite value, or the norm of its rotation quaternion is not close to 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ValidationPolicy {
+    /// Reject invalid transforms with [`TfError::InvalidArgument`], like tf2 does
+    #[default]
+    Reject,
+    /// Normalize rotation quaternions which are not of unit length, and reject all other invalid
+    /// transforms
+    Normalize,
+    /// Log a warning, but insert invalid transforms anyway, including those with NaN values or a
+    /// zero-norm rotation quaternion, which may make the lookups through them return NaN
+    Warn,
+}
+
+enum Violation {
+    Invalid(String),
+    UnnormalizedQuaternion(f64),
+}
+
+/// Returns the transform to insert according to the policy, or an error if it is rejected
+pub(crate) fn validate_transform(
+    transform: &TransformStamped,
+    policy: ValidationPolicy,
+) -> Result<Cow<'_, TransformStamped>, TfError> {
+    let Err(violation) = check_transform(transform) else {
+        return Ok(Cow::Borrowed(transform));
+    };
+    match (policy, violation) {
+        (ValidationPolicy::Normalize, Violation::UnnormalizedQuaternion(norm)) => {
+            let mut transform = transform.clone();
+            let rotation = &mut transform.transform.rotation;
+            rotation.x /= norm;
+            rotation.y /= norm;
+            rotation.z /= norm;
+            rotation.w /= norm;
+            Ok(Cow::Owned(transform))
+        }
+        (ValidationPolicy::Warn, violation) => {
+            r2r::log_warn!("tf_r2r", "{}", describe(transform, &violation));
+            Ok(Cow::Borrowed(transform))
+        }
+        (_, violation) => Err(TfError::InvalidArgument(describe(transform, &violation))),
+    }
+}
+
+fn check_transform(transform: &TransformStamped) -> Result<(), Violation> {
+    let parent = &transform.header.frame_id;
+    let child = &transform.child_frame_id;
+    if parent.is_empty() || child.is_empty() {
+        return Err(Violation::Invalid("has an empty frame ID".to_string()));
+    }
+    if parent == child {
+        return Err(Violation::Invalid(
+            "has the same parent and child frame".to_string(),
+        ));
+    }
+    let translation = &transform.transform.translation;
+    let rotation = &transform.transform.rotation;
+    let values = [
+        translation.x,
+        translation.y,
+        translation.z,
+        rotation.x,
+        rotation.y,
+        rotation.z,
+        rotation.w,
+    ];
+    if !values.iter().all(|value| value.is_finite()) {
+        return Err(Violation::Invalid(
+            "contains a NaN or infinite value".to_string(),
+        ));
+    }
+    let norm_squared = rotation.x * rotation.x
+        + rotation.y * rotation.y
+        + rotation.z * rotation.z
+        + rotation.w * rotation.w;
+    if norm_squared < f64::EPSILON {
+        return Err(Violation::Invalid("has a zero quaternion".to_string()));
+    }
+    if (norm_squared - 1.0).abs() > QUATERNION_TOLERANCE {
+        return Err(Violation::UnnormalizedQuaternion(norm_squared.sqrt()));
+    }
+    Ok(())
+}
+
+fn describe(transform: &TransformStamped, violation: &Violation) -> String {
+    let reason = match violation {
+        Violation::Invalid(reason) => reason.clone(),
+        Violation::UnnormalizedQuaternion(norm) => {
+            format!("has an unnormalized quaternion ({norm})")
+        }
+    };
+    format!(
+        "transform from {:?} to {:?} {reason}",
+        transform.header.frame_id, transform.child_frame_id
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::transform;
+
+    #[test]
+    fn test_valid_transform() {
+        for policy in [
+            ValidationPolicy::Reject,
+            ValidationPolicy::Normalize,
+            ValidationPolicy::Warn,
+        ] {
+            let tf = transform("odom", "base_link", 0, 0.0);
+            assert!(matches!(
+                validate_transform(&tf, policy),
+                Ok(Cow::Borrowed(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_invalid_transforms() {
+        let mut empty = transform("odom", "base_link", 0, 0.0);
+        empty.header.frame_id.clear();
+        let mut same = transform("odom", "base_link", 0, 0.0);
+        same.child_frame_id = "odom".to_string();
+        let mut nan = transform("odom", "base_link", 0, 0.0);
+        nan.transform.translation.x = f64::NAN;
+        let mut infinite = transform("odom", "base_link", 0, 0.0);
+        infinite.transform.rotation.z = f64::INFINITY;
+        let mut zero = transform("odom", "base_link", 0, 0.0);
+        zero.transform.rotation.w = 0.0;
+
+        for tf in [empty, same, nan, infinite, zero] {
+            for policy in [ValidationPolicy::Reject, ValidationPolicy::Normalize] {
+                assert!(matches!(
+                    validate_transform(&tf, policy),
+                    Err(TfError::InvalidArgument(_))
+                ));
+            }
+            assert!(validate_transform(&tf, ValidationPolicy::Warn).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_unnormalized_quaternion() {
+        let mut tf = transform("odom", "base_link", 0, 0.0);
+        tf.transform.rotation.z = 2.0;
+        tf.transform.rotation.w = 2.0;
+        assert!(validate_transform(&tf, ValidationPolicy::Reject).is_err());
+        assert_eq!(
+            validate_transform(&tf, ValidationPolicy::Warn)
+                .unwrap()
+                .as_ref(),
+            &tf
+        );
+        let normalized = validate_transform(&tf, ValidationPolicy::Normalize).unwrap();
+        let rotation = &normalized.transform.rotation;
+        assert!((rotation.z - 0.5_f64.sqrt()).abs() < 1e-9);
+        assert!((rotation.w - 0.5_f64.sqrt()).abs() < 1e-9);
+
+        // Small deviations are accepted as they are
+        tf.transform.rotation.z = 0.0;
+        tf.transform.rotation.w = 1.001;
+        assert!(matches!(
+            validate_transform(&tf, ValidationPolicy::Normalize),
+            Ok(Cow::Borrowed(_))
+        ));
+    }
+}
@@ -4,62 +4,27 @@ const BILLION: i64 = 1_000_000_000;
 
 pub(crate) fn time_from_nanosec(t: i64) -> Time {
     Time {
-        sec: (t / BILLION) as i32,
-        nanosec: (t % BILLION) as u32,
-    }
-}
-
-/// target - delta
-pub(crate) fn sub_time_and_time(target: &Time, delta: &Time) -> Duration {
-    if delta.sec.is_positive() {
-        if target.nanosec >= delta.nanosec {
-            Duration {
-                sec: target.sec - delta.sec,
-                nanosec: target.nanosec - delta.nanosec,
-            }
-        } else {
-            Duration {
-                sec: target.sec - delta.sec - 1,
-                nanosec: BILLION as u32 + target.nanosec - delta.nanosec,
-            }
-        }
-    } else {
-        let nanosec = target.nanosec + delta.nanosec;
-        let sec = target.sec - delta.sec + nanosec as i32 / BILLION as i32;
-        let nanosec = nanosec % BILLION as u32;
-        Duration { sec, nanosec }
+        sec: t.div_euclid(BILLION) as i32,
+        nanosec: t.rem_euclid(BILLION) as u32,
     }
 }
 
 pub(crate) fn add_time_and_duration(t: &Time, d: &Duration) -> Time {
-    let sec = t.sec + d.sec;
-    let nanosec = t.nanosec + d.nanosec;
-
-    Time {
-        sec: sec + nanosec as i32 / BILLION as i32,
-        nanosec: nanosec % BILLION as u32,
-    }
+    time_from_nanosec(time_as_ns_i64(t) + duration_as_ns_i64(d))
 }
 
 pub(crate) fn sub_duration_from_time(t: &Time, d: &Duration) -> Time {
-    let mut sec = t.sec - d.sec;
-    let mut nanosec = t.nanosec as i64 - d.nanosec as i64;
-
-    if nanosec.is_negative() {
-        sec -= 1;
-        nanosec += BILLION;
-    }
-
-    Time {
-        sec,
-        nanosec: nanosec as u32,
-    }
+    time_from_nanosec(time_as_ns_i64(t) - duration_as_ns_i64(d))
 }
 
 pub(crate) fn time_as_ns_i64(t: &Time) -> i64 {
     t.sec as i64 * BILLION + t.nanosec as i64
 }
 
+pub(crate) fn duration_as_ns_i64(d: &Duration) -> i64 {
+    d.sec as i64 * BILLION + d.nanosec as i64
+}
+
 pub(crate) fn is_time_in_range_eq(target: &Time, min: &Time, max: &Time) -> bool {
     let target_i64 = target.sec as i64 * BILLION + target.nanosec as i64;
     let min_i64 = min.sec as i64 * BILLION + min.nanosec as i64;
@@ -90,35 +55,10 @@ mod test {
 
         assert_eq!(time.sec, EXPECTED_TIME.sec);
         assert_eq!(time.nanosec, EXPECTED_TIME.nanosec);
-    }
 
-    #[test]
-    fn test_sub_time_and_time() {
-        const EXPECTED_DIF1: Time = Time {
-            sec: 0,
-            nanosec: 111_111_011,
-        };
-        const EXPECTED_DIF2: Time = Time {
-            sec: 0,
-            nanosec: 888_889_889,
-        };
-        const EXPECTED_DIF3: Time = Time {
-            sec: 20,
-            nanosec: 691_357_802,
-        };
-
-        let (t1, t2, t3, t4, _, _) = times_and_durations_for_test();
-
-        let dif1 = sub_time_and_time(&t2, &t1);
-        let dif2 = sub_time_and_time(&t2, &t3);
-        let dif3 = sub_time_and_time(&t2, &t4);
-
-        assert_eq!(dif1.sec, EXPECTED_DIF1.sec);
-        assert_eq!(dif1.nanosec, EXPECTED_DIF1.nanosec);
-        assert_eq!(dif2.sec, EXPECTED_DIF2.sec);
-        assert_eq!(dif2.nanosec, EXPECTED_DIF2.nanosec);
-        assert_eq!(dif3.sec, EXPECTED_DIF3.sec);
-        assert_eq!(dif3.nanosec, EXPECTED_DIF3.nanosec);
+        let time = time_from_nanosec(-1_500_000_000);
+        assert_eq!(time.sec, -2);
+        assert_eq!(time.nanosec, 500_000_000);
     }
 
     #[test]
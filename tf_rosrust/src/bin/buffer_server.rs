@@ -0,0 +1,26 @@
+//! Runs a `TfListener` and answers `tf2_msgs/LookupTransform` action goals in the
+//! `tf2_buffer_server` namespace, like `buffer_server` of tf2_ros, so that other nodes can look up
+//! transforms through a `BufferClient` instead of subscribing to `/tf`. The frame tree is also
+//! served on `~tf2_frames`.
+//!
+//! Usage: `buffer_server [cache seconds]`, which defaults to 120 seconds like tf2_ros.
+
+use std::error::Error;
+
+use tf_rosrust::{BufferServer, TfBuffer, TfListener};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    rosrust::init("tf2_buffer_server");
+    let mut args = rosrust::args().into_iter().skip(1);
+    let seconds: f64 = args.next().map_or(Ok(120.0), |arg| arg.parse())?;
+
+    let buffer = TfBuffer::new_with_duration(rosrust::Duration::from_nanos((seconds * 1e9) as i64));
+    let listener = TfListener::builder()
+        .buffer(buffer)
+        .frame_graph_service("~tf2_frames")
+        .build()?;
+    let _server = BufferServer::new(listener, "tf2_buffer_server")?;
+    rosrust::ros_info!("Answering LookupTransform goals with a cache of {seconds} seconds");
+    rosrust::spin();
+    Ok(())
+}
@@ -0,0 +1,25 @@
+//! Listens to the transforms for a few seconds, and writes the frame tree as Graphviz DOT and
+//! Mermaid files, like `view_frames` of tf2.
+//!
+//! Usage: `view_frames [seconds] [file name without extension]`, which default to 5 seconds and
+//! `frames`, writing `frames.gv` and `frames.mmd`.
+
+use std::{error::Error, fs};
+
+use tf_rosrust::TfListener;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    rosrust::init("view_frames");
+    let mut args = rosrust::args().into_iter().skip(1);
+    let seconds: f64 = args.next().map_or(Ok(5.0), |arg| arg.parse())?;
+    let name = args.next().unwrap_or_else(|| "frames".to_string());
+
+    let listener = TfListener::new();
+    rosrust::ros_info!("Listening to the transforms for {seconds} seconds");
+    rosrust::sleep(rosrust::Duration::from_nanos((seconds * 1e9) as i64));
+    let buffer = listener.buffer();
+    fs::write(format!("{name}.gv"), buffer.frames_as_dot())?;
+    fs::write(format!("{name}.mmd"), buffer.frames_as_mermaid())?;
+    rosrust::ros_info!("Wrote {name}.gv and {name}.mmd");
+    Ok(())
+}
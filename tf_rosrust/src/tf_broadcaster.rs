@@ -1,5 +1,6 @@
 use crate::{
     tf_error::TfError,
+    tf_listener::namespaced_topics,
     transforms::{geometry_msgs::TransformStamped, tf2_msgs::TFMessage},
 };
 
@@ -35,21 +36,39 @@ pub struct TfBroadcaster {
 
 impl TfBroadcaster {
     /// Create a new TfBroadcaster
+    ///
+    /// # Panics
+    ///
+    /// Panics if the publisher could not be created. Use [`TfBroadcaster::try_new`] to handle
+    /// the error instead.
+    #[track_caller]
     pub fn new() -> Self {
-        Self {
-            publisher: rosrust::publish("/tf", 1000).unwrap(),
-        }
+        Self::try_new().unwrap()
+    }
+
+    /// Create a new TfBroadcaster, returning an error if the publisher could not be created.
+    pub fn try_new() -> Result<Self, TfError> {
+        Self::builder().build()
+    }
+
+    /// Returns a builder to configure the topic and queue size of a TfBroadcaster
+    pub fn builder() -> TfBroadcasterBuilder {
+        TfBroadcasterBuilder::default()
     }
 
     /// Broadcast transform
     pub fn send_transform(&self, tf: TransformStamped) -> Result<(), TfError> {
-        let tf_message = TFMessage {
-            transforms: vec![tf],
-        };
-        // TODO: handle error correctly
+        self.send_transforms(vec![tf])
+    }
+
+    /// Broadcast multiple transforms in a single message
+    pub fn send_transforms(&self, transforms: Vec<TransformStamped>) -> Result<(), TfError> {
+        if transforms.is_empty() {
+            return Ok(());
+        }
         self.publisher
-            .send(tf_message)
-            .map_err(|err| TfError::Rosrust(err.description().to_string()))
+            .send(TFMessage { transforms })
+            .map_err(rosrust_error)
     }
 }
 
@@ -58,3 +77,67 @@ impl Default for TfBroadcaster {
         TfBroadcaster::new()
     }
 }
+
+/// Builder of [`TfBroadcaster`]
+#[derive(Debug)]
+#[must_use]
+pub struct TfBroadcasterBuilder {
+    topic: String,
+    queue_size: usize,
+}
+
+impl Default for TfBroadcasterBuilder {
+    fn default() -> Self {
+        Self {
+            topic: "/tf".to_string(),
+            queue_size: 1000,
+        }
+    }
+}
+
+impl TfBroadcasterBuilder {
+    /// Sets the topic to publish to. The default is the global `/tf` like in tf2, unlike the
+    /// [`TfListenerBuilder`](crate::TfListenerBuilder) which subscribes to `tf` in the namespace of
+    /// the node by default. Both name the same topic for nodes in the root namespace.
+    pub fn topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = topic.into();
+        self
+    }
+
+    /// Publishes to `tf` in the given namespace
+    pub fn namespace(self, namespace: &str) -> Self {
+        self.topic(namespaced_topics(namespace).0)
+    }
+
+    /// Sets the queue size of the publisher. The default is 1000.
+    pub fn queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size;
+        self
+    }
+
+    /// Creates the TfBroadcaster
+    pub fn build(self) -> Result<TfBroadcaster, TfError> {
+        if !rosrust::is_initialized() {
+            return Err(TfError::Rosrust("rosrust is not initialized".to_string()));
+        }
+        Ok(TfBroadcaster {
+            publisher: rosrust::publish(&self.topic, self.queue_size).map_err(rosrust_error)?,
+        })
+    }
+}
+
+fn rosrust_error(err: rosrust::error::Error) -> TfError {
+    TfError::Rosrust(err.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_topic() {
+        // The listener subscribes to the relative `tf` by default, see `TfListenerBuilder::topics`.
+        assert_eq!(TfBroadcasterBuilder::default().topic, "/tf");
+        assert_eq!(TfBroadcasterBuilder::default().namespace("/").topic, "/tf");
+    }
+}
@@ -1,11 +1,23 @@
-use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+use std::{
+    borrow::Cow,
+    collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
+    mem,
+    sync::Arc,
+};
 
 use rosrust::Duration;
 
 use crate::{
+    tf_cache_limits::{CacheLimits, CacheOverride},
+    tf_conflict::{ConflictMonitor, ConflictTolerances, TfConflict},
     tf_error::TfError,
+    tf_frame_graph,
+    tf_frame_normalization::FrameNormalization,
     tf_graph_node::TfGraphNode,
     tf_individual_transform_chain::TfIndividualTransformChain,
+    tf_parent_history::{ParentHistory, ParentInterval},
+    tf_sample_policy::{SamplePolicy, SampleStatistics},
+    tf_validation::{validate_transform, ValidationPolicy},
     transforms::{
         chain_transforms,
         geometry_msgs::{Transform, TransformStamped},
@@ -14,13 +26,45 @@ use crate::{
         tf2_msgs::TFMessage,
         to_transform_stamped,
     },
+    utils::warn,
 };
 
 #[derive(Clone, Debug)]
 pub struct TfBuffer {
     child_transform_index: HashMap<String, HashSet<String>>,
-    transform_data: HashMap<TfGraphNode, TfIndividualTransformChain>,
-    cache_duration: Duration,
+    // The chains are shared between the snapshots of a `SharedTfBuffer`. A modified chain is
+    // copied, but keeps sharing most of its samples.
+    transform_data: HashMap<TfGraphNode, Arc<TfIndividualTransformChain>>,
+    cache_limits: CacheLimits,
+    // Checked in order, the first override matching the child frame of an edge applies
+    cache_overrides: Vec<CacheOverride>,
+    validation_policy: ValidationPolicy,
+    sample_policy: SamplePolicy,
+    frame_normalization: FrameNormalization,
+    // Frame IDs which were received before normalization, by normalized frame ID
+    frame_aliases: HashMap<String, HashSet<String>>,
+    // Parents over time, by child frame ID
+    frame_parents: HashMap<String, ParentHistory>,
+    conflict_monitor: ConflictMonitor,
+    conflict_tolerances: ConflictTolerances,
+    // Newest stamp of the dynamic transforms, and its value when the expired edges were last
+    // removed, in nanoseconds
+    newest_stamp_nanos: i64,
+    pruned_stamp_nanos: i64,
+}
+
+/// A frame and the edge from its current parent, as described by tf2
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FrameInfo {
+    pub(crate) frame_id: String,
+    pub(crate) parent: String,
+    pub(crate) authority: String,
+    /// Stamps of the oldest and newest stored transforms in seconds
+    pub(crate) oldest_stamp: f64,
+    pub(crate) newest_stamp: f64,
+    pub(crate) samples: usize,
+    /// Stored transforms per second, computed like tf2 does
+    pub(crate) rate: f64,
 }
 
 const DEFAULT_CACHE_DURATION_SECONDS: i32 = 10;
@@ -34,18 +78,408 @@ impl TfBuffer {
         TfBuffer {
             child_transform_index: HashMap::new(),
             transform_data: HashMap::new(),
-            cache_duration,
+            cache_limits: CacheLimits::new(cache_duration),
+            cache_overrides: Vec::new(),
+            validation_policy: ValidationPolicy::default(),
+            sample_policy: SamplePolicy::default(),
+            frame_normalization: FrameNormalization::default(),
+            frame_aliases: HashMap::new(),
+            frame_parents: HashMap::new(),
+            conflict_monitor: ConflictMonitor::default(),
+            conflict_tolerances: ConflictTolerances::default(),
+            newest_stamp_nanos: 0,
+            pruned_stamp_nanos: 0,
+        }
+    }
+
+    /// Limits the number of transforms kept for each edge. By default, the number is only limited
+    /// by the cache duration.
+    pub fn with_max_samples(mut self, max_samples: usize) -> Self {
+        self.cache_limits.max_samples = Some(max_samples);
+        self
+    }
+
+    /// Sets the cache limits of the edges whose child frame matches the pattern, in which `*`
+    /// matches any sequence of characters, e.g. `odom` or `*_joint`
+    ///
+    /// The pattern is matched against the normalized frame IDs. If several patterns match, the
+    /// first one added applies.
+    pub fn with_cache_override(mut self, pattern: impl Into<String>, limits: CacheLimits) -> Self {
+        self.cache_overrides
+            .push(CacheOverride::new(pattern.into(), limits));
+        self
+    }
+
+    /// Returns an estimate of the memory used by the stored transforms in bytes
+    ///
+    /// Transforms shared with other snapshots of a [`SharedTfBuffer`](crate::SharedTfBuffer) are
+    /// counted too.
+    pub fn memory_usage(&self) -> usize {
+        self.transform_data
+            .iter()
+            .map(|(node, chain)| {
+                mem::size_of::<(TfGraphNode, Arc<TfIndividualTransformChain>)>()
+                    + node.child.capacity()
+                    + node.parent.capacity()
+                    + chain.memory_usage()
+            })
+            .sum()
+    }
+
+    /// Sets how invalid transforms are handled. The default is [`ValidationPolicy::Reject`].
+    pub fn with_validation_policy(mut self, validation_policy: ValidationPolicy) -> Self {
+        self.validation_policy = validation_policy;
+        self
+    }
+
+    /// Sets how repeated and late transforms are handled. By default, they are dropped.
+    pub fn with_sample_policy(mut self, sample_policy: SamplePolicy) -> Self {
+        self.sample_policy = sample_policy;
+        self
+    }
+
+    /// Sets how frame IDs are normalized. By default, leading slashes are stripped.
+    pub fn with_frame_normalization(mut self, frame_normalization: FrameNormalization) -> Self {
+        self.frame_normalization = frame_normalization;
+        self
+    }
+
+    /// Returns the frame IDs which were changed by the normalization, by normalized frame ID
+    ///
+    /// Frames which are published under several names appear with all of them.
+    pub fn frame_aliases(&self) -> &HashMap<String, HashSet<String>> {
+        &self.frame_aliases
+    }
+
+    /// Returns the counters of the transforms received between the frames
+    pub fn sample_statistics(&self, parent: &str, child: &str) -> Option<SampleStatistics> {
+        let node = TfGraphNode {
+            child: self.frame_normalization.normalize(child).into_owned(),
+            parent: self.frame_normalization.normalize(parent).into_owned(),
+        };
+        self.transform_data
+            .get(&node)
+            .map(|chain| chain.statistics())
+    }
+
+    /// Sets a callback which is called for every detected [`TfConflict`]
+    ///
+    /// The callback is called while the transform is inserted, so it must not update the
+    /// [`SharedTfBuffer`](crate::SharedTfBuffer) containing this buffer. It is shared by all
+    /// clones of the buffer.
+    pub fn with_conflict_callback(
+        mut self,
+        callback: impl Fn(&TfConflict) + Send + Sync + 'static,
+    ) -> Self {
+        self.conflict_monitor.set_callback(callback);
+        self
+    }
+
+    /// Sets the differences above which transforms of competing authorities are reported as
+    /// [`TfConflict::CompetingAuthorities`]
+    pub fn with_conflict_tolerances(mut self, conflict_tolerances: ConflictTolerances) -> Self {
+        self.conflict_tolerances = conflict_tolerances;
+        self
+    }
+
+    /// Returns the latest conflicts, oldest first
+    pub fn conflicts(&self) -> &VecDeque<TfConflict> {
+        self.conflict_monitor.conflicts()
+    }
+
+    /// Forgets the conflicts detected so far
+    pub fn clear_conflicts(&mut self) {
+        self.conflict_monitor.clear();
+    }
+
+    /// Returns the authority which published the latest transform between the frames
+    pub fn authority(&self, parent: &str, child: &str) -> Option<&str> {
+        let node = TfGraphNode {
+            child: self.frame_normalization.normalize(child).into_owned(),
+            parent: self.frame_normalization.normalize(parent).into_owned(),
+        };
+        self.transform_data
+            .get(&node)
+            .map(|chain| chain.authority())
+    }
+
+    /// Returns the authority which published the stored transform of an extrapolation error,
+    /// i.e. [`TfError::AttemptedLookupInPast`] or [`TfError::AttemptedLookUpInFuture`]
+    ///
+    /// This is the authority of the latest transform between the frames of the stored one, so the
+    /// error should be passed to the buffer or snapshot which returned it.
+    pub fn error_authority(&self, err: &TfError) -> Option<&str> {
+        match err {
+            TfError::AttemptedLookupInPast(_, transform)
+            | TfError::AttemptedLookUpInFuture(transform, _) => {
+                self.authority(&transform.header.frame_id, &transform.child_frame_id)
+            }
+            _ => None,
         }
     }
 
-    pub(crate) fn handle_incoming_transforms(&mut self, transforms: TFMessage, static_tf: bool) {
+    /// Returns the parents of the frame over time, oldest first
+    ///
+    /// Lookups at a given time use the parent of each frame at that time, so that an object which
+    /// was re-parented, e.g. from a table to a gripper, is not looked up through a stale edge.
+    pub fn parent_intervals(&self, frame_id: &str) -> Vec<ParentInterval> {
+        self.frame_parents
+            .get(self.frame_normalization.normalize(frame_id).as_ref())
+            .map(|history| history.intervals())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn handle_incoming_transforms(
+        &mut self,
+        transforms: TFMessage,
+        authority: &str,
+        static_tf: bool,
+    ) {
         for transform in transforms.transforms {
-            self.add_transform(&transform, static_tf);
-            self.add_transform(&get_inverse(&transform), static_tf);
+            if let Err(err) = self.set_transform(&transform, authority, static_tf) {
+                warn(err);
+            }
+        }
+    }
+
+    /// Adds the transform to the buffer, after normalizing its frame IDs according to the
+    /// [`FrameNormalization`] and validating it according to the [`ValidationPolicy`].
+    ///
+    /// The authority, usually the name of the node which published the transform, is recorded
+    /// for the edge between the frames.
+    pub fn set_transform(
+        &mut self,
+        transform: &TransformStamped,
+        authority: &str,
+        static_tf: bool,
+    ) -> Result<(), TfError> {
+        let transform = self.normalize_frames(transform);
+        let transform = validate_transform(&transform, self.validation_policy)?;
+        self.detect_conflicts(&transform, authority);
+        self.add_transform(&transform, authority, static_tf)?;
+        self.add_transform(&get_inverse(&transform), authority, static_tf)?;
+        if !static_tf {
+            self.newest_stamp_nanos = self.newest_stamp_nanos.max(transform.header.stamp.nanos());
+        }
+        self.record_parent(&transform, authority, static_tf);
+        if !static_tf {
+            // Checking all edges once per shortest cache duration is enough to keep the buffer
+            // bounded.
+            if self.newest_stamp_nanos - self.pruned_stamp_nanos >= self.prune_interval_nanos() {
+                self.remove_expired_edges();
+                self.pruned_stamp_nanos = self.newest_stamp_nanos;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the frame and all edges from or to it. Returns `false` if the frame did not exist.
+    pub fn remove_frame(&mut self, frame_id: &str) -> bool {
+        let frame_id = self.frame_normalization.normalize(frame_id).into_owned();
+        let Some(neighbors) = self.child_transform_index.get(&frame_id).cloned() else {
+            return false;
+        };
+        for neighbor in neighbors {
+            self.remove_edge(&frame_id, &neighbor);
+            self.remove_edge(&neighbor, &frame_id);
+        }
+        true
+    }
+
+    /// Removes the frame and all frames below it in the tree, and returns the removed frames
+    ///
+    /// The tree is given by the current parent of each frame.
+    pub fn remove_subtree(&mut self, frame_id: &str) -> Vec<String> {
+        let frame_id = self.frame_normalization.normalize(frame_id).into_owned();
+        let mut visited = HashSet::from([frame_id.clone()]);
+        let mut subtree = vec![frame_id];
+        let mut index = 0;
+        while let Some(frame_id) = subtree.get(index) {
+            let children: Vec<_> = self
+                .frame_parents
+                .iter()
+                .filter(|(child, history)| {
+                    history.current_parent() == Some(frame_id) && !visited.contains(*child)
+                })
+                .map(|(child, _)| child.clone())
+                .collect();
+            visited.extend(children.iter().cloned());
+            subtree.extend(children);
+            index += 1;
+        }
+        subtree.retain(|frame_id| self.child_transform_index.contains_key(frame_id));
+        for frame_id in &subtree {
+            self.remove_frame(frame_id);
+        }
+        subtree
+    }
+
+    /// Removes the dynamic edges whose samples are all older than the cache duration
+    /// Returns the shortest cache duration of any edge, including the overrides
+    fn prune_interval_nanos(&self) -> i64 {
+        self.cache_overrides
+            .iter()
+            .map(|cache_override| cache_override.limits.duration.nanos())
+            .fold(self.cache_limits.duration.nanos(), i64::min)
+    }
+
+    fn remove_expired_edges(&mut self) {
+        let expired: Vec<_> = self
+            .transform_data
+            .iter()
+            .filter(|(_, chain)| chain.is_expired(self.newest_stamp_nanos))
+            .map(|(node, _)| node.clone())
+            .collect();
+        for node in expired {
+            self.remove_edge(&node.parent, &node.child);
+        }
+    }
+
+    fn remove_edge(&mut self, parent: &str, child: &str) {
+        self.transform_data.remove(&TfGraphNode {
+            child: child.to_string(),
+            parent: parent.to_string(),
+        });
+        if let Some(children) = self.child_transform_index.get_mut(parent) {
+            children.remove(child);
+            if children.is_empty() {
+                self.child_transform_index.remove(parent);
+            }
+        }
+        if let Some(history) = self.frame_parents.get_mut(child) {
+            history.retain_parents(|p| p != parent);
+            if history.is_empty() {
+                self.frame_parents.remove(child);
+            }
+        }
+    }
+
+    fn normalize_frames<'a>(
+        &mut self,
+        transform: &'a TransformStamped,
+    ) -> Cow<'a, TransformStamped> {
+        let parent = self.normalize_frame(&transform.header.frame_id);
+        let child = self.normalize_frame(&transform.child_frame_id);
+        if parent.is_none() && child.is_none() {
+            return Cow::Borrowed(transform);
+        }
+        let mut transform = transform.clone();
+        if let Some(parent) = parent {
+            transform.header.frame_id = parent;
+        }
+        if let Some(child) = child {
+            transform.child_frame_id = child;
+        }
+        Cow::Owned(transform)
+    }
+
+    /// Removes all dynamic transforms, and keeps the static ones
+    ///
+    /// The listeners call this when the time jumps backward, e.g. when a bag is played in a loop.
+    pub fn clear(&mut self) {
+        self.newest_stamp_nanos = 0;
+        self.pruned_stamp_nanos = 0;
+        self.transform_data.retain(|_, chain| chain.is_static());
+        self.child_transform_index.clear();
+        for node in self.transform_data.keys() {
+            self.child_transform_index
+                .entry(node.parent.clone())
+                .or_default()
+                .insert(node.child.clone());
+        }
+        let transform_data = &self.transform_data;
+        self.frame_parents.retain(|child, history| {
+            history.retain_parents(|parent| {
+                transform_data.contains_key(&TfGraphNode {
+                    child: child.clone(),
+                    parent: parent.to_string(),
+                })
+            });
+            !history.is_empty()
+        });
+    }
+
+    fn detect_conflicts(&mut self, transform: &TransformStamped, authority: &str) {
+        let parent = &transform.header.frame_id;
+        let child = &transform.child_frame_id;
+        let node = TfGraphNode {
+            child: child.clone(),
+            parent: parent.clone(),
+        };
+        if let Some(conflict) = self
+            .transform_data
+            .get(&node)
+            .and_then(|chain| chain.find_conflict(transform, authority, &self.conflict_tolerances))
+        {
+            self.conflict_monitor.report(conflict);
+        }
+    }
+
+    /// Records the parent of the child frame at the stamp of the transform, and reports
+    /// re-parenting
+    fn record_parent(&mut self, transform: &TransformStamped, authority: &str, static_tf: bool) {
+        let parent = &transform.header.frame_id;
+        let child = &transform.child_frame_id;
+        // Static transforms are valid at all times.
+        let stamp_nanos = if static_tf {
+            0
+        } else {
+            transform.header.stamp.nanos()
+        };
+        let oldest_nanos =
+            self.newest_stamp_nanos - self.edge_cache_limits(parent, child).duration.nanos();
+        let history = self.frame_parents.entry(child.clone()).or_default();
+        if let Some(previous_parent) = history.insert(parent, authority, stamp_nanos) {
+            self.conflict_monitor.report(TfConflict::Reparented {
+                child: child.clone(),
+                previous_parent,
+                parent: parent.clone(),
+                stamp: transform.header.stamp,
+                authority: authority.to_string(),
+            });
+        }
+        history.remove_before(oldest_nanos);
+    }
+
+    /// Returns whether the edge between the frames belongs to the tree at the given time
+    ///
+    /// Edges which were not inserted with [`Self::set_transform`] have no recorded parent, and are
+    /// always part of the tree.
+    fn is_edge_active(&self, frame_a: &str, frame_b: &str, time_nanos: i64) -> bool {
+        let is_parent = |child: &str, parent: &str| {
+            self.frame_parents
+                .get(child)
+                .map(|history| history.parent_at(time_nanos) == Some(parent))
+        };
+        match (is_parent(frame_b, frame_a), is_parent(frame_a, frame_b)) {
+            (None, None) => true,
+            (b_is_child, a_is_child) => b_is_child == Some(true) || a_is_child == Some(true),
         }
     }
 
-    fn add_transform(&mut self, transform: &TransformStamped, static_tf: bool) {
+    /// Returns the normalized frame ID if it differs, and records it as an alias
+    fn normalize_frame(&mut self, frame_id: &str) -> Option<String> {
+        let normalized = self.frame_normalization.normalize(frame_id);
+        if normalized == frame_id {
+            return None;
+        }
+        let normalized = normalized.into_owned();
+        let aliases = self.frame_aliases.entry(normalized.clone()).or_default();
+        if !aliases.contains(frame_id) {
+            warn(format!(
+                "frame {frame_id:?} was normalized to {normalized:?}"
+            ));
+            aliases.insert(frame_id.to_string());
+        }
+        Some(normalized)
+    }
+
+    fn add_transform(
+        &mut self,
+        transform: &TransformStamped,
+        authority: &str,
+        static_tf: bool,
+    ) -> Result<(), TfError> {
         //TODO: Detect is new transform will create a loop
         self.child_transform_index
             .entry(transform.header.frame_id.clone())
@@ -57,14 +491,31 @@ impl TfBuffer {
             parent: transform.header.frame_id.clone(),
         };
 
-        match self.transform_data.entry(key) {
+        let limits = self.edge_cache_limits(&key.parent, &key.child);
+        let chain = match self.transform_data.entry(key) {
             Entry::Occupied(e) => e.into_mut(),
-            Entry::Vacant(e) => e.insert(TfIndividualTransformChain::new(
-                static_tf,
-                self.cache_duration,
+            Entry::Vacant(e) => e.insert(Arc::new(
+                TfIndividualTransformChain::new(static_tf, limits.duration)
+                    .with_max_samples(limits.max_samples),
             )),
+        };
+        Arc::make_mut(chain).add_to_buffer(transform.clone(), authority, self.sample_policy)
+    }
+
+    /// Returns the cache limits of a new edge
+    fn edge_cache_limits(&self, parent: &str, child: &str) -> CacheLimits {
+        // The inverse of an edge is inserted right after it, and gets the same limits.
+        let inverse = TfGraphNode {
+            child: parent.to_string(),
+            parent: child.to_string(),
+        };
+        if let Some(chain) = self.transform_data.get(&inverse) {
+            return chain.limits();
         }
-        .add_to_buffer(transform.clone());
+        self.cache_overrides
+            .iter()
+            .find(|cache_override| cache_override.matches(child))
+            .map_or(self.cache_limits, |cache_override| cache_override.limits)
     }
 
     /// Retrieves the transform path
@@ -81,8 +532,7 @@ impl TfBuffer {
         visited.insert(from.clone());
         frontier.push_front(from.clone());
 
-        while !frontier.is_empty() {
-            let current_node = frontier.pop_front().unwrap();
+        while let Some(current_node) = frontier.pop_front() {
             if current_node == to {
                 break;
             }
@@ -99,6 +549,7 @@ impl TfBuffer {
                             parent: current_node.clone(),
                         })
                         .is_some_and(|chain| chain.has_valid_transform(time))
+                        && self.is_edge_active(&current_node, v, time.nanos())
                     {
                         parents.insert(v.to_string(), current_node.clone());
                         frontier.push_front(v.to_string());
@@ -127,6 +578,122 @@ impl TfBuffer {
         Ok(res)
     }
 
+    /// Returns the frames of the tree, sorted
+    pub fn get_frames(&self) -> Vec<String> {
+        let mut frames: Vec<_> = self.child_transform_index.keys().cloned().collect();
+        frames.sort();
+        frames
+    }
+
+    /// Returns whether the frame is part of the tree
+    pub fn frame_exists(&self, frame_id: &str) -> bool {
+        self.child_transform_index
+            .contains_key(self.frame_normalization.normalize(frame_id).as_ref())
+    }
+
+    /// Returns the parent of the frame at the given time, or its current parent if the time is
+    /// zero
+    pub fn get_parent(&self, frame_id: &str, time: rosrust::Time) -> Option<&str> {
+        self.frame_parents
+            .get(self.frame_normalization.normalize(frame_id).as_ref())
+            .and_then(|history| history.parent_at(time.nanos()))
+    }
+
+    /// Returns the frames traversed by a lookup from `source` to `target` at the given time,
+    /// including both
+    pub fn get_chain(
+        &self,
+        source: &str,
+        target: &str,
+        time: rosrust::Time,
+    ) -> Result<Vec<String>, TfError> {
+        let source = self.frame_normalization.normalize(source).into_owned();
+        let target = self.frame_normalization.normalize(target).into_owned();
+        let mut chain = vec![source.clone()];
+        chain.extend(self.retrieve_transform_path(source, target, time)?);
+        Ok(chain)
+    }
+
+    /// Describes the parent of each frame, like `allFramesAsString` of tf2
+    pub fn all_frames_as_string(&self) -> String {
+        self.frame_infos()
+            .iter()
+            .map(|info| {
+                format!(
+                    "Frame {} exists with parent {}.\n",
+                    info.frame_id, info.parent
+                )
+            })
+            .collect()
+    }
+
+    /// Describes the parent, authority, oldest and newest stamps, and publish rate of each frame
+    /// in YAML, like `allFramesAsYAML` of tf2, but with quoted frame IDs
+    pub fn all_frames_as_yaml(&self) -> String {
+        let infos = self.frame_infos();
+        if infos.is_empty() {
+            return "[]".to_string();
+        }
+        infos
+            .iter()
+            .map(|info| {
+                format!(
+                    "{}: \n  parent: {}\n  broadcaster: {}\n  rate: {:.3}\n  \
+                     most_recent_transform: {:.3}\n  oldest_transform: {:.3}\n  \
+                     buffer_length: {:.3}\n",
+                    tf_frame_graph::quote_yaml(&info.frame_id),
+                    tf_frame_graph::quote_yaml(&info.parent),
+                    tf_frame_graph::quote_yaml(&info.authority),
+                    info.rate,
+                    info.newest_stamp,
+                    info.oldest_stamp,
+                    info.newest_stamp - info.oldest_stamp,
+                )
+            })
+            .collect()
+    }
+
+    /// Renders the frame tree as a Graphviz DOT graph, like `view_frames` of tf2
+    ///
+    /// Each edge is labeled with its authority, publish rate, newest stamp and buffer length.
+    pub fn frames_as_dot(&self) -> String {
+        tf_frame_graph::to_dot(&self.frame_infos())
+    }
+
+    /// Renders the frame tree as a Mermaid flowchart, labeled like [`Self::frames_as_dot`]
+    pub fn frames_as_mermaid(&self) -> String {
+        tf_frame_graph::to_mermaid(&self.frame_infos())
+    }
+
+    /// Describes the frames which have a parent, sorted by frame ID
+    pub(crate) fn frame_infos(&self) -> Vec<FrameInfo> {
+        let mut infos: Vec<_> = self
+            .frame_parents
+            .iter()
+            .filter_map(|(child, history)| {
+                let parent = history.current_parent()?;
+                let chain = self.transform_data.get(&TfGraphNode {
+                    child: child.clone(),
+                    parent: parent.to_string(),
+                })?;
+                let oldest_stamp = chain.oldest_stamp()?.seconds();
+                let newest_stamp = chain.newest_stamp()?.seconds();
+                let samples = chain.transform_chain.len();
+                Some(FrameInfo {
+                    frame_id: child.clone(),
+                    parent: parent.to_string(),
+                    authority: chain.authority().to_string(),
+                    oldest_stamp,
+                    newest_stamp,
+                    samples,
+                    rate: samples as f64 / (newest_stamp - oldest_stamp).max(0.0001),
+                })
+            })
+            .collect();
+        infos.sort_by(|a, b| a.frame_id.cmp(&b.frame_id));
+        infos
+    }
+
     /// Looks up a transform within the tree at a given time.
     pub fn lookup_transform(
         &self,
@@ -134,8 +701,8 @@ impl TfBuffer {
         to: &str,
         time: rosrust::Time,
     ) -> Result<TransformStamped, TfError> {
-        let from = from.to_string();
-        let to = to.to_string();
+        let from = self.frame_normalization.normalize(from).into_owned();
+        let to = self.frame_normalization.normalize(to).into_owned();
         let path = self.retrieve_transform_path(from.clone(), to.clone(), time);
 
         match path {
@@ -147,8 +714,10 @@ impl TfBuffer {
                         child: intermediate.clone(),
                         parent: first.clone(),
                     };
-                    let time_cache = self.transform_data.get(&node).unwrap();
-                    let transform = time_cache.get_closest_transform(time);
+                    let transform = match self.transform_data.get(&node) {
+                        Some(time_cache) => time_cache.get_closest_transform(&node, time),
+                        None => Err(TfError::EmptyTransformChain(node.parent, node.child)),
+                    };
                     match transform {
                         Err(e) => return Err(e),
                         Ok(x) => {
@@ -187,8 +756,8 @@ impl TfBuffer {
         let result = chain_transforms(&[tf2.transform, transforms.transform]);
         Ok(to_transform_stamped(
             result,
-            from.to_string(),
-            to.to_string(),
+            self.frame_normalization.normalize(from).into_owned(),
+            self.frame_normalization.normalize(to).into_owned(),
             time1,
         ))
     }
@@ -199,11 +768,16 @@ mod test {
     use rosrust::Time;
 
     use super::*;
-    use crate::transforms::geometry_msgs::{Quaternion, Vector3};
+    use crate::{
+        test_utils::transform,
+        tf_frame_graph::parse_frames_yaml,
+        transforms::geometry_msgs::{Quaternion, Vector3},
+    };
 
     const PARENT: &str = "parent";
     const CHILD0: &str = "child0";
     const CHILD1: &str = "child1";
+    const AUTHORITY: &str = "authority";
 
     /// This function builds a tree consisting of the following items:
     /// * a world coordinate frame
@@ -237,8 +811,12 @@ mod test {
                 },
             },
         };
-        buffer.add_transform(&world_to_item, true);
-        buffer.add_transform(&get_inverse(&world_to_item), true);
+        buffer
+            .add_transform(&world_to_item, AUTHORITY, true)
+            .unwrap();
+        buffer
+            .add_transform(&get_inverse(&world_to_item), AUTHORITY, true)
+            .unwrap();
 
         let world_to_base_link = TransformStamped {
             child_frame_id: "base_link".to_string(),
@@ -264,8 +842,12 @@ mod test {
                 },
             },
         };
-        buffer.add_transform(&world_to_base_link, false);
-        buffer.add_transform(&get_inverse(&world_to_base_link), false);
+        buffer
+            .add_transform(&world_to_base_link, AUTHORITY, false)
+            .unwrap();
+        buffer
+            .add_transform(&get_inverse(&world_to_base_link), AUTHORITY, false)
+            .unwrap();
 
         let base_link_to_camera = TransformStamped {
             child_frame_id: "camera".to_string(),
@@ -291,8 +873,12 @@ mod test {
                 },
             },
         };
-        buffer.add_transform(&base_link_to_camera, true);
-        buffer.add_transform(&get_inverse(&base_link_to_camera), true);
+        buffer
+            .add_transform(&base_link_to_camera, AUTHORITY, true)
+            .unwrap();
+        buffer
+            .add_transform(&get_inverse(&base_link_to_camera), AUTHORITY, true)
+            .unwrap();
     }
 
     /// Tests a basic lookup
@@ -450,7 +1036,9 @@ mod test {
             parent: PARENT.to_owned(),
         };
         let static_tf = true;
-        tf_buffer.add_transform(&transform00, static_tf);
+        tf_buffer
+            .add_transform(&transform00, AUTHORITY, static_tf)
+            .unwrap();
         assert_eq!(tf_buffer.child_transform_index.len(), 1);
         assert!(tf_buffer.child_transform_index.contains_key(PARENT));
         let children = tf_buffer.child_transform_index.get(PARENT).unwrap();
@@ -462,7 +1050,9 @@ mod test {
         assert!(data.is_some());
         assert_eq!(data.unwrap().transform_chain.len(), 1);
 
-        tf_buffer.add_transform(&transform01, static_tf);
+        tf_buffer
+            .add_transform(&transform01, AUTHORITY, static_tf)
+            .unwrap();
         assert_eq!(tf_buffer.child_transform_index.len(), 1);
         assert!(tf_buffer.child_transform_index.contains_key(PARENT));
         let children = tf_buffer.child_transform_index.get(PARENT).unwrap();
@@ -474,7 +1064,9 @@ mod test {
         assert!(data.is_some());
         assert_eq!(data.unwrap().transform_chain.len(), 2);
 
-        tf_buffer.add_transform(&transform1, static_tf);
+        tf_buffer
+            .add_transform(&transform1, AUTHORITY, static_tf)
+            .unwrap();
         assert_eq!(tf_buffer.child_transform_index.len(), 1);
         assert!(tf_buffer.child_transform_index.contains_key(PARENT));
         let children = tf_buffer.child_transform_index.get(PARENT).unwrap();
@@ -528,7 +1120,9 @@ mod test {
         };
 
         let static_tf = true;
-        tf_buffer.add_transform(&transform00, static_tf);
+        tf_buffer
+            .add_transform(&transform00, AUTHORITY, static_tf)
+            .unwrap();
         assert_eq!(tf_buffer.child_transform_index.len(), 1);
         assert_eq!(tf_buffer.transform_data.len(), 1);
         assert!(tf_buffer.transform_data.contains_key(&transform0_key));
@@ -536,11 +1130,13 @@ mod test {
         assert!(data.is_some());
         assert_eq!(data.unwrap().transform_chain.len(), 1);
         assert_eq!(
-            data.unwrap().transform_chain[0].header.stamp,
+            data.unwrap().transform_chain.first().unwrap().header.stamp,
             Time::from_nanos(0)
         );
 
-        tf_buffer.add_transform(&transform01, static_tf);
+        tf_buffer
+            .add_transform(&transform01, AUTHORITY, static_tf)
+            .unwrap();
         assert_eq!(tf_buffer.child_transform_index.len(), 1);
         assert_eq!(tf_buffer.transform_data.len(), 1);
         assert!(tf_buffer.transform_data.contains_key(&transform0_key));
@@ -548,15 +1144,17 @@ mod test {
         assert!(data.is_some());
         assert_eq!(data.unwrap().transform_chain.len(), 2);
         assert_eq!(
-            data.unwrap().transform_chain[0].header.stamp,
+            data.unwrap().transform_chain.first().unwrap().header.stamp,
             Time::from_nanos(0)
         );
         assert_eq!(
-            data.unwrap().transform_chain[1].header.stamp,
+            data.unwrap().transform_chain.last().unwrap().header.stamp,
             Time::from_nanos(1_000_000_000)
         );
 
-        tf_buffer.add_transform(&transform02, static_tf);
+        tf_buffer
+            .add_transform(&transform02, AUTHORITY, static_tf)
+            .unwrap();
         assert_eq!(tf_buffer.child_transform_index.len(), 1);
         assert_eq!(tf_buffer.transform_data.len(), 1);
         assert!(tf_buffer.transform_data.contains_key(&transform0_key));
@@ -564,11 +1162,11 @@ mod test {
         assert!(data.is_some());
         assert_eq!(data.unwrap().transform_chain.len(), 2);
         assert_eq!(
-            data.unwrap().transform_chain[0].header.stamp,
+            data.unwrap().transform_chain.first().unwrap().header.stamp,
             Time::from_nanos(1_000_000_000)
         );
         assert_eq!(
-            data.unwrap().transform_chain[1].header.stamp,
+            data.unwrap().transform_chain.last().unwrap().header.stamp,
             Time::from_nanos(2_000_000_000)
         );
     }
@@ -616,8 +1214,12 @@ mod test {
                 },
             },
         };
-        tf_buffer.add_transform(&base_to_camera1, true);
-        tf_buffer.add_transform(&get_inverse(&base_to_camera1), true);
+        tf_buffer
+            .add_transform(&base_to_camera1, AUTHORITY, true)
+            .unwrap();
+        tf_buffer
+            .add_transform(&get_inverse(&base_to_camera1), AUTHORITY, true)
+            .unwrap();
 
         let base_to_camera2 = TransformStamped {
             child_frame_id: "camera2".to_string(),
@@ -640,8 +1242,12 @@ mod test {
                 },
             },
         };
-        tf_buffer.add_transform(&base_to_camera2, true);
-        tf_buffer.add_transform(&get_inverse(&base_to_camera2), true);
+        tf_buffer
+            .add_transform(&base_to_camera2, AUTHORITY, true)
+            .unwrap();
+        tf_buffer
+            .add_transform(&get_inverse(&base_to_camera2), AUTHORITY, true)
+            .unwrap();
 
         let marker_to_target = TransformStamped {
             child_frame_id: "target".to_string(),
@@ -664,8 +1270,12 @@ mod test {
                 },
             },
         };
-        tf_buffer.add_transform(&marker_to_target, true);
-        tf_buffer.add_transform(&get_inverse(&marker_to_target), true);
+        tf_buffer
+            .add_transform(&marker_to_target, AUTHORITY, true)
+            .unwrap();
+        tf_buffer
+            .add_transform(&get_inverse(&marker_to_target), AUTHORITY, true)
+            .unwrap();
 
         let mut camera1_to_marker = TransformStamped {
             child_frame_id: "marker".to_string(),
@@ -688,14 +1298,22 @@ mod test {
                 },
             },
         };
-        tf_buffer.add_transform(&camera1_to_marker, false);
-        tf_buffer.add_transform(&get_inverse(&camera1_to_marker), false);
+        tf_buffer
+            .add_transform(&camera1_to_marker, AUTHORITY, false)
+            .unwrap();
+        tf_buffer
+            .add_transform(&get_inverse(&camera1_to_marker), AUTHORITY, false)
+            .unwrap();
 
         camera1_to_marker.header.stamp.sec = 2;
         camera1_to_marker.header.seq += 1;
         camera1_to_marker.transform.translation.y = -1.0;
-        tf_buffer.add_transform(&camera1_to_marker, false);
-        tf_buffer.add_transform(&get_inverse(&camera1_to_marker), false);
+        tf_buffer
+            .add_transform(&camera1_to_marker, AUTHORITY, false)
+            .unwrap();
+        tf_buffer
+            .add_transform(&get_inverse(&camera1_to_marker), AUTHORITY, false)
+            .unwrap();
 
         let mut camera2_to_marker = TransformStamped {
             child_frame_id: "marker".to_string(),
@@ -718,14 +1336,22 @@ mod test {
                 },
             },
         };
-        tf_buffer.add_transform(&camera2_to_marker, false);
-        tf_buffer.add_transform(&get_inverse(&camera2_to_marker), false);
+        tf_buffer
+            .add_transform(&camera2_to_marker, AUTHORITY, false)
+            .unwrap();
+        tf_buffer
+            .add_transform(&get_inverse(&camera2_to_marker), AUTHORITY, false)
+            .unwrap();
 
         camera2_to_marker.header.stamp.sec = 4;
         camera2_to_marker.header.seq += 1;
         camera2_to_marker.transform.translation.y = -1.0;
-        tf_buffer.add_transform(&camera2_to_marker, false);
-        tf_buffer.add_transform(&get_inverse(&camera2_to_marker), false);
+        tf_buffer
+            .add_transform(&camera2_to_marker, AUTHORITY, false)
+            .unwrap();
+        tf_buffer
+            .add_transform(&get_inverse(&camera2_to_marker), AUTHORITY, false)
+            .unwrap();
 
         let result =
             tf_buffer.lookup_transform("base", "target", rosrust::Time { sec: 1, nsec: 0 });
@@ -829,14 +1455,22 @@ mod test {
         camera1_to_marker.header.seq += 1;
         camera1_to_marker.transform.translation.x = 0.5;
         camera1_to_marker.transform.translation.y = 1.0;
-        tf_buffer.add_transform(&camera1_to_marker, false);
-        tf_buffer.add_transform(&get_inverse(&camera1_to_marker), false);
+        tf_buffer
+            .add_transform(&camera1_to_marker, AUTHORITY, false)
+            .unwrap();
+        tf_buffer
+            .add_transform(&get_inverse(&camera1_to_marker), AUTHORITY, false)
+            .unwrap();
 
         camera1_to_marker.header.stamp.sec = 6;
         camera1_to_marker.header.seq += 1;
         camera1_to_marker.transform.translation.y = -1.0;
-        tf_buffer.add_transform(&camera1_to_marker, false);
-        tf_buffer.add_transform(&get_inverse(&camera1_to_marker), false);
+        tf_buffer
+            .add_transform(&camera1_to_marker, AUTHORITY, false)
+            .unwrap();
+        tf_buffer
+            .add_transform(&get_inverse(&camera1_to_marker), AUTHORITY, false)
+            .unwrap();
 
         let result =
             tf_buffer.lookup_transform("base", "target", rosrust::Time { sec: 5, nsec: 0 });
@@ -877,4 +1511,402 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn test_set_transform_validation() {
+        let mut tf = transform("odom", "base_link", 0, 0.0);
+        tf.transform.rotation.w = 2.0;
+
+        let mut tf_buffer = TfBuffer::new();
+        assert!(matches!(
+            tf_buffer.set_transform(&tf, AUTHORITY, false),
+            Err(TfError::InvalidArgument(_))
+        ));
+        assert!(tf_buffer.transform_data.is_empty());
+
+        let mut tf_buffer = TfBuffer::new().with_validation_policy(ValidationPolicy::Normalize);
+        tf_buffer.set_transform(&tf, AUTHORITY, false).unwrap();
+        let result = tf_buffer.lookup_transform("odom", "base_link", rosrust::Time::new());
+        assert_eq!(result.unwrap().transform.rotation.w, 1.0);
+
+        let mut tf_buffer = TfBuffer::new().with_validation_policy(ValidationPolicy::Warn);
+        tf_buffer.set_transform(&tf, AUTHORITY, false).unwrap();
+        assert_eq!(tf_buffer.transform_data.len(), 2);
+    }
+
+    #[test]
+    fn test_frame_normalization() {
+        let tf = transform("/odom", "base_link", 0, 0.0);
+
+        let mut tf_buffer = TfBuffer::new();
+        tf_buffer.set_transform(&tf, AUTHORITY, false).unwrap();
+        let result = tf_buffer.lookup_transform("odom", "/base_link", rosrust::Time::new());
+        assert_eq!(result.unwrap().header.frame_id, "odom");
+        assert_eq!(
+            tf_buffer.frame_aliases(),
+            &HashMap::from([("odom".to_string(), HashSet::from(["/odom".to_string()]))])
+        );
+
+        let mut tf_buffer = TfBuffer::new().with_frame_normalization(FrameNormalization::none());
+        tf_buffer.set_transform(&tf, AUTHORITY, false).unwrap();
+        assert!(tf_buffer
+            .lookup_transform("odom", "base_link", rosrust::Time::new())
+            .is_err());
+        assert!(tf_buffer.frame_aliases().is_empty());
+    }
+
+    #[test]
+    fn test_authority() {
+        let mut tf = transform("odom", "base_link", 0, 0.0);
+
+        let mut tf_buffer = TfBuffer::new();
+        assert_eq!(tf_buffer.authority("odom", "base_link"), None);
+        tf_buffer.set_transform(&tf, "/publisher1", false).unwrap();
+        tf.header.stamp.sec = 1;
+        tf_buffer.set_transform(&tf, "/publisher2", false).unwrap();
+        assert_eq!(
+            tf_buffer.authority("odom", "base_link"),
+            Some("/publisher2")
+        );
+        assert_eq!(
+            tf_buffer.authority("/base_link", "odom"),
+            Some("/publisher2")
+        );
+
+        let node = TfGraphNode {
+            child: "base_link".to_string(),
+            parent: "odom".to_string(),
+        };
+        let err = tf_buffer.transform_data[&node]
+            .get_closest_transform(&node, rosrust::Time { sec: 5, nsec: 0 })
+            .unwrap_err();
+        assert!(matches!(err, TfError::AttemptedLookUpInFuture(..)));
+        assert_eq!(tf_buffer.error_authority(&err), Some("/publisher2"));
+        assert_eq!(tf_buffer.error_authority(&TfError::Cancelled), None);
+    }
+
+    #[test]
+    fn test_conflict_tolerances() {
+        let mut tf_buffer = TfBuffer::new().with_conflict_tolerances(ConflictTolerances {
+            translation: 0.01,
+            ..Default::default()
+        });
+        tf_buffer
+            .set_transform(&transform("odom", "base_link", 20, 0.0), "/node1", false)
+            .unwrap();
+        tf_buffer
+            .set_transform(&transform("odom", "base_link", 21, 0.05), "/node2", false)
+            .unwrap();
+        assert!(matches!(
+            tf_buffer.conflicts().back(),
+            Some(TfConflict::CompetingAuthorities { authority, .. }) if authority == "/node2"
+        ));
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let reported = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut tf_buffer = {
+            let reported = reported.clone();
+            TfBuffer::new().with_conflict_callback(move |conflict| {
+                reported.lock().unwrap().push(conflict.clone());
+            })
+        };
+        let mut set_transform = |tf: TransformStamped, authority: &str| {
+            tf_buffer.set_transform(&tf, authority, false).unwrap();
+        };
+        set_transform(transform("odom", "base_link", 20, 0.0), "/node1");
+        set_transform(transform("odom", "base_link", 21, 0.0), "/node1");
+        set_transform(transform("odom", "base_link", 21, 0.0), "/node1");
+        set_transform(transform("odom", "base_link", 5, 0.0), "/node1");
+        set_transform(transform("odom", "base_link", 22, 1.0), "/node2");
+        set_transform(transform("map", "base_link", 22, 0.0), "/node2");
+
+        let conflicts: Vec<_> = tf_buffer.conflicts().iter().cloned().collect();
+        assert!(matches!(
+            conflicts.as_slice(),
+            [
+                TfConflict::RepeatedData { .. },
+                TfConflict::OldData { .. },
+                TfConflict::CompetingAuthorities { .. },
+                TfConflict::Reparented { previous_parent, parent, .. },
+            ] if previous_parent == "odom" && parent == "map"
+        ));
+        assert_eq!(*reported.lock().unwrap(), conflicts);
+        assert_eq!(
+            tf_buffer.sample_statistics("odom", "base_link"),
+            Some(SampleStatistics {
+                received: 5,
+                duplicates: 1,
+                late: 1,
+            })
+        );
+        tf_buffer.clear_conflicts();
+        assert!(tf_buffer.conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut tf_buffer = TfBuffer::new();
+        tf_buffer
+            .set_transform(&transform("map", "odom", 0, 0.0), AUTHORITY, true)
+            .unwrap();
+        tf_buffer
+            .set_transform(&transform("odom", "base_link", 0, 0.0), AUTHORITY, false)
+            .unwrap();
+        tf_buffer.clear();
+        assert!(tf_buffer
+            .lookup_transform("map", "odom", rosrust::Time::new())
+            .is_ok());
+        assert!(tf_buffer
+            .lookup_transform("odom", "base_link", rosrust::Time::new())
+            .is_err());
+        assert_eq!(tf_buffer.transform_data.len(), 2);
+        assert_eq!(tf_buffer.child_transform_index.len(), 2);
+        assert_eq!(tf_buffer.frame_parents.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_frame() {
+        let mut tf_buffer = TfBuffer::new();
+        for (parent, child) in [
+            ("map", "odom"),
+            ("odom", "base_link"),
+            ("base_link", "camera"),
+        ] {
+            tf_buffer
+                .set_transform(&transform(parent, child, 1, 0.0), AUTHORITY, false)
+                .unwrap();
+        }
+        assert!(tf_buffer.remove_frame("/base_link"));
+        assert!(!tf_buffer.remove_frame("base_link"));
+        assert!(tf_buffer
+            .lookup_transform("map", "odom", rosrust::Time::new())
+            .is_ok());
+        assert!(tf_buffer
+            .lookup_transform("odom", "base_link", rosrust::Time::new())
+            .is_err());
+        assert_eq!(tf_buffer.transform_data.len(), 2);
+        assert!(!tf_buffer.child_transform_index.contains_key("camera"));
+        assert_eq!(tf_buffer.frame_parents.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_subtree() {
+        let mut tf_buffer = TfBuffer::new();
+        for (parent, child) in [
+            ("map", "odom"),
+            ("odom", "base_link"),
+            ("base_link", "camera"),
+            ("base_link", "lidar"),
+        ] {
+            tf_buffer
+                .set_transform(&transform(parent, child, 1, 0.0), AUTHORITY, true)
+                .unwrap();
+        }
+        let mut removed = tf_buffer.remove_subtree("odom");
+        removed.sort();
+        assert_eq!(removed, ["base_link", "camera", "lidar", "odom"]);
+        assert!(tf_buffer.transform_data.is_empty());
+        assert!(tf_buffer.child_transform_index.is_empty());
+        assert!(tf_buffer.frame_parents.is_empty());
+        assert!(tf_buffer.remove_subtree("odom").is_empty());
+    }
+
+    #[test]
+    fn test_remove_expired_edges() {
+        let mut tf_buffer = TfBuffer::new();
+        tf_buffer
+            .set_transform(&transform("map", "odom", 1, 0.0), AUTHORITY, true)
+            .unwrap();
+        tf_buffer
+            .set_transform(&transform("odom", "object_1", 1, 0.0), AUTHORITY, false)
+            .unwrap();
+        tf_buffer
+            .set_transform(&transform("odom", "base_link", 25, 0.0), AUTHORITY, false)
+            .unwrap();
+        assert!(tf_buffer
+            .lookup_transform("odom", "object_1", rosrust::Time::new())
+            .is_err());
+        assert_eq!(tf_buffer.transform_data.len(), 4);
+        assert_eq!(tf_buffer.frame_parents.len(), 2);
+        assert!(!tf_buffer.child_transform_index.contains_key("object_1"));
+    }
+
+    #[test]
+    fn test_remove_expired_edges_with_override() {
+        let mut tf_buffer = TfBuffer::new()
+            .with_cache_override("object_*", CacheLimits::new(Duration::from_seconds(1)));
+        tf_buffer
+            .set_transform(&transform("odom", "object_1", 1, 0.0), AUTHORITY, false)
+            .unwrap();
+        tf_buffer
+            .set_transform(&transform("odom", "base_link", 3, 0.0), AUTHORITY, false)
+            .unwrap();
+        // The edge of the override expires long before the default cache duration has passed.
+        assert!(!tf_buffer.child_transform_index.contains_key("object_1"));
+        assert_eq!(tf_buffer.transform_data.len(), 2);
+    }
+
+    #[test]
+    fn test_reparenting() {
+        let mut tf_buffer = TfBuffer::new();
+        for (parent, x) in [("table", 1.0), ("gripper", 2.0)] {
+            let tf = transform("world", parent, 0, x);
+            tf_buffer.set_transform(&tf, AUTHORITY, true).unwrap();
+        }
+        for (parent, sec) in [("table", 1), ("table", 2), ("gripper", 3), ("gripper", 4)] {
+            let tf = transform(parent, "object", sec, 0.1);
+            tf_buffer.set_transform(&tf, "/picker", false).unwrap();
+        }
+        let x_at = |time| {
+            tf_buffer
+                .lookup_transform("world", "object", time)
+                .unwrap()
+                .transform
+                .translation
+                .x
+        };
+        assert_eq!(
+            x_at(Time {
+                sec: 1,
+                nsec: 500_000_000
+            }),
+            1.1
+        );
+        assert_eq!(
+            x_at(Time {
+                sec: 3,
+                nsec: 500_000_000
+            }),
+            2.1
+        );
+        assert_eq!(x_at(Time::new()), 2.1);
+
+        let intervals = tf_buffer.parent_intervals("/object");
+        assert_eq!(intervals.len(), 2);
+        assert_eq!(intervals[0].parent, "table");
+        assert_eq!(intervals[0].end, Some(Time { sec: 3, nsec: 0 }));
+        assert_eq!(intervals[1].parent, "gripper");
+        assert_eq!(intervals[1].authority, "/picker");
+        assert!(matches!(
+            tf_buffer.conflicts().back(),
+            Some(TfConflict::Reparented { stamp, .. }) if stamp.sec == 3
+        ));
+    }
+
+    #[test]
+    fn test_cache_limits() {
+        let mut tf_buffer = TfBuffer::new()
+            .with_max_samples(100)
+            .with_cache_override("odom", CacheLimits::new(Duration::from_seconds(60)))
+            .with_cache_override(
+                "*_joint",
+                CacheLimits::new(Duration::from_seconds(2)).with_max_samples(3),
+            );
+        assert_eq!(tf_buffer.memory_usage(), 0);
+        for sec in 0..30 {
+            for (parent, child) in [
+                ("map", "odom"),
+                ("odom", "base_link"),
+                ("base_link", "elbow_joint"),
+            ] {
+                tf_buffer
+                    .set_transform(&transform(parent, child, sec, 0.0), AUTHORITY, false)
+                    .unwrap();
+            }
+        }
+        let samples = |parent: &str, child: &str| {
+            tf_buffer.transform_data[&TfGraphNode {
+                child: child.to_string(),
+                parent: parent.to_string(),
+            }]
+                .transform_chain
+                .len()
+        };
+        assert_eq!(samples("map", "odom"), 30);
+        assert_eq!(samples("odom", "map"), 30);
+        // The inverse edge is not matched by the child frame pattern
+        assert_eq!(samples("odom", "base_link"), 11);
+        assert_eq!(samples("base_link", "odom"), 11);
+        assert_eq!(samples("base_link", "elbow_joint"), 3);
+        assert_eq!(samples("elbow_joint", "base_link"), 3);
+
+        let memory_usage = tf_buffer.memory_usage();
+        assert!(memory_usage > 88 * mem::size_of::<TransformStamped>());
+        tf_buffer.remove_frame("elbow_joint");
+        assert!(tf_buffer.memory_usage() < memory_usage);
+    }
+
+    #[test]
+    fn test_introspection() {
+        let mut tf_buffer = TfBuffer::new();
+        assert_eq!(tf_buffer.all_frames_as_yaml(), "[]");
+        tf_buffer
+            .set_transform(&transform("map", "odom", 0, 0.0), AUTHORITY, true)
+            .unwrap();
+        for sec in 1..=3 {
+            tf_buffer
+                .set_transform(
+                    &transform("odom", "base_link", sec, 0.0),
+                    "/odometry",
+                    false,
+                )
+                .unwrap();
+        }
+        tf_buffer
+            .set_transform(&transform("base_link", "camera", 0, 0.0), AUTHORITY, true)
+            .unwrap();
+
+        assert_eq!(
+            tf_buffer.get_frames(),
+            ["base_link", "camera", "map", "odom"]
+        );
+        assert!(tf_buffer.frame_exists("/odom"));
+        assert!(!tf_buffer.frame_exists("lidar"));
+        assert_eq!(tf_buffer.get_parent("base_link", Time::new()), Some("odom"));
+        assert_eq!(tf_buffer.get_parent("map", Time::new()), None);
+        assert_eq!(
+            tf_buffer
+                .get_chain("map", "camera", Time { sec: 2, nsec: 0 })
+                .unwrap(),
+            ["map", "odom", "base_link", "camera"]
+        );
+        assert!(tf_buffer
+            .get_chain("map", "lidar", Time { sec: 2, nsec: 0 })
+            .is_err());
+
+        assert_eq!(
+            tf_buffer.all_frames_as_string(),
+            "Frame base_link exists with parent odom.\n\
+             Frame camera exists with parent base_link.\n\
+             Frame odom exists with parent map.\n"
+        );
+        assert!(tf_buffer.all_frames_as_yaml().contains(
+            "'base_link': \n  parent: 'odom'\n  broadcaster: '/odometry'\n  rate: 1.500\n  \
+             most_recent_transform: 3.000\n  oldest_transform: 1.000\n  buffer_length: 2.000\n"
+        ));
+    }
+
+    #[test]
+    fn test_frames_yaml_round_trip() {
+        let mut tf_buffer = TfBuffer::new();
+        let frames = ["map", "a: b", "#c", "*d", "&e", "it's"];
+        for pair in frames.windows(2) {
+            tf_buffer
+                .set_transform(&transform(pair[0], pair[1], 0, 0.0), AUTHORITY, true)
+                .unwrap();
+        }
+        let yaml = tf_buffer.all_frames_as_yaml();
+        assert!(yaml.contains("'#c': \n  parent: 'a: b'\n"));
+        assert!(yaml.contains("'it''s': \n  parent: '&e'\n"));
+        let parsed = parse_frames_yaml(&yaml).unwrap();
+        let mut expected: Vec<_> = frames.windows(2).map(|pair| (pair[1], pair[0])).collect();
+        expected.sort();
+        let parsed: Vec<_> = parsed
+            .iter()
+            .map(|frame| (frame.frame_id.as_str(), frame.parent.as_str()))
+            .collect();
+        assert_eq!(parsed, expected);
+    }
 }
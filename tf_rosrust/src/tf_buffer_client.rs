@@ -0,0 +1,333 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Condvar, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    tf_error::TfError,
+    transforms::{
+        actionlib_msgs::{GoalID, GoalStatus},
+        geometry_msgs::TransformStamped,
+        std_msgs::Header,
+        tf2_msgs::{
+            LookupTransformActionGoal, LookupTransformActionResult, LookupTransformGoal, TF2Error,
+        },
+    },
+};
+
+const DEFAULT_TIMEOUT_PADDING: Duration = Duration::from_secs(2);
+const QUEUE_SIZE: usize = 100;
+
+/// Number of goals sent by all the clients of the process, which makes their goal IDs unique even
+/// if the time does not change
+static NEXT_GOAL: AtomicU64 = AtomicU64::new(0);
+
+/// Looks up transforms through a [`BufferServer`](crate::BufferServer) or a `BufferServer` of
+/// tf2_ros, like `BufferClient` of tf2_ros
+///
+/// This has the same lookup API as [`TfListener`](crate::TfListener), but does not subscribe to
+/// `/tf`, which keeps the traffic and memory of the node low. Each lookup sends a
+/// `tf2_msgs/LookupTransform` goal and blocks until its result is received.
+///
+/// ```no_run
+/// use tf_rosrust::BufferClient;
+///
+/// rosrust::init("client");
+/// let client = BufferClient::new("/tf2_buffer_server").unwrap();
+/// client
+///     .wait_for_server(std::time::Duration::from_secs(5))
+///     .unwrap();
+/// let tf = client.lookup_transform("camera", "base_link", rosrust::Time::new());
+/// println!("{tf:?}");
+/// ```
+///
+/// Errors of the server are returned as [`TfError::BufferServer`].
+pub struct BufferClient {
+    goal_publisher: rosrust::Publisher<LookupTransformActionGoal>,
+    cancel_publisher: rosrust::Publisher<GoalID>,
+    result_subscriber: rosrust::Subscriber,
+    pending_goals: Arc<PendingGoals>,
+    timeout_padding: Duration,
+}
+
+/// Results of the goals sent by a client, by goal ID
+#[derive(Default)]
+struct PendingGoals {
+    results: Mutex<HashMap<String, Option<LookupTransformActionResult>>>,
+    received: Condvar,
+}
+
+impl BufferClient {
+    /// Creates a client of the action in the given namespace
+    pub fn new(namespace: &str) -> Result<Self, TfError> {
+        let namespace = namespace.trim_end_matches('/');
+        let pending_goals = Arc::new(PendingGoals::default());
+        let p1 = pending_goals.clone();
+        let result_subscriber = rosrust::subscribe(
+            &format!("{namespace}/result"),
+            QUEUE_SIZE,
+            move |result: LookupTransformActionResult| p1.complete(result),
+        )
+        .map_err(rosrust_error)?;
+        Ok(Self {
+            goal_publisher: rosrust::publish(&format!("{namespace}/goal"), QUEUE_SIZE)
+                .map_err(rosrust_error)?,
+            cancel_publisher: rosrust::publish(&format!("{namespace}/cancel"), QUEUE_SIZE)
+                .map_err(rosrust_error)?,
+            result_subscriber,
+            pending_goals,
+            timeout_padding: DEFAULT_TIMEOUT_PADDING,
+        })
+    }
+
+    /// Sets how long the client waits for a result in addition to the timeout of the lookup. The
+    /// default is 2 seconds, like in tf2_ros.
+    pub fn with_timeout_padding(mut self, timeout_padding: Duration) -> Self {
+        self.timeout_padding = timeout_padding;
+        self
+    }
+
+    /// Blocks until the server is connected or the timeout elapses
+    pub fn wait_for_server(&self, timeout: Duration) -> Result<(), TfError> {
+        let deadline = Instant::now() + timeout;
+        while self.goal_publisher.subscriber_count() == 0
+            || self.result_subscriber.publisher_count() == 0
+        {
+            if Instant::now() >= deadline {
+                return Err(TfError::BufferServer(
+                    TF2Error::TIMEOUT_ERROR,
+                    "the buffer server is not connected".to_string(),
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+
+    /// Looks up a transform within the tree at a given time.
+    pub fn lookup_transform(
+        &self,
+        from: &str,
+        to: &str,
+        time: rosrust::Time,
+    ) -> Result<TransformStamped, TfError> {
+        self.wait_for_transform(from, to, time, Duration::ZERO)
+    }
+
+    /// Blocks until the transform is available or the timeout elapses.
+    pub fn wait_for_transform(
+        &self,
+        from: &str,
+        to: &str,
+        time: rosrust::Time,
+        timeout: Duration,
+    ) -> Result<TransformStamped, TfError> {
+        self.send_goal(LookupTransformGoal {
+            target_frame: from.to_string(),
+            source_frame: to.to_string(),
+            source_time: time,
+            timeout: goal_timeout(timeout),
+            ..Default::default()
+        })
+    }
+
+    /// Looks up a transform within the tree at a given time.
+    pub fn lookup_transform_with_time_travel(
+        &self,
+        from: &str,
+        time1: rosrust::Time,
+        to: &str,
+        time2: rosrust::Time,
+        fixed_frame: &str,
+    ) -> Result<TransformStamped, TfError> {
+        self.send_goal(LookupTransformGoal {
+            target_frame: from.to_string(),
+            target_time: time1,
+            source_frame: to.to_string(),
+            source_time: time2,
+            fixed_frame: fixed_frame.to_string(),
+            advanced: true,
+            ..Default::default()
+        })
+    }
+
+    /// Sends the goal and blocks until its result is received, or cancels it if the timeout of
+    /// the goal and the padding elapse first
+    fn send_goal(&self, goal: LookupTransformGoal) -> Result<TransformStamped, TfError> {
+        let now = rosrust::now();
+        let goal_id = GoalID {
+            stamp: now,
+            id: format!(
+                "{}-{}-{}",
+                rosrust::name(),
+                NEXT_GOAL.fetch_add(1, Ordering::Relaxed),
+                now.seconds()
+            ),
+        };
+        let timeout = Duration::from_nanos(goal.timeout.nanos().max(0) as u64)
+            .saturating_add(self.timeout_padding);
+        self.pending_goals.insert(&goal_id.id);
+        let sent = self.goal_publisher.send(LookupTransformActionGoal {
+            header: Header {
+                stamp: now,
+                ..Default::default()
+            },
+            goal_id: goal_id.clone(),
+            goal,
+        });
+        if let Err(err) = sent {
+            self.pending_goals.remove(&goal_id.id);
+            return Err(rosrust_error(err));
+        }
+        match self.pending_goals.wait(&goal_id.id, timeout) {
+            Some(result) => transform_from_result(result),
+            None => {
+                let _ = self.cancel_publisher.send(goal_id);
+                Err(TfError::BufferServer(
+                    TF2Error::TIMEOUT_ERROR,
+                    "the buffer server did not answer in time".to_string(),
+                ))
+            }
+        }
+    }
+}
+
+impl PendingGoals {
+    fn insert(&self, goal_id: &str) {
+        self.lock_results().insert(goal_id.to_string(), None);
+    }
+
+    fn remove(&self, goal_id: &str) {
+        self.lock_results().remove(goal_id);
+    }
+
+    /// Stores the result if its goal is pending. The results of the goals of other clients are
+    /// ignored.
+    fn complete(&self, result: LookupTransformActionResult) {
+        if let Some(pending) = self.lock_results().get_mut(&result.status.goal_id.id) {
+            *pending = Some(result);
+            self.received.notify_all();
+        }
+    }
+
+    /// Blocks until the result of the goal is received or the timeout elapses, and removes the
+    /// goal
+    fn wait(&self, goal_id: &str, timeout: Duration) -> Option<LookupTransformActionResult> {
+        let results = self.lock_results();
+        let mut results = self
+            .received
+            .wait_timeout_while(results, timeout, |results| {
+                matches!(results.get(goal_id), Some(None))
+            })
+            .unwrap_or_else(PoisonError::into_inner)
+            .0;
+        results.remove(goal_id).flatten()
+    }
+
+    fn lock_results(
+        &self,
+    ) -> std::sync::MutexGuard<'_, HashMap<String, Option<LookupTransformActionResult>>> {
+        self.results.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Converts the timeout of a lookup to the timeout of its goal, saturating at the longest timeout
+/// a goal can hold
+fn goal_timeout(timeout: Duration) -> rosrust::Duration {
+    match i32::try_from(timeout.as_secs()) {
+        Ok(sec) => rosrust::Duration {
+            sec,
+            nsec: timeout.subsec_nanos() as i32,
+        },
+        Err(_) => rosrust::Duration {
+            sec: i32::MAX,
+            nsec: 999_999_999,
+        },
+    }
+}
+
+fn transform_from_result(result: LookupTransformActionResult) -> Result<TransformStamped, TfError> {
+    if result.status.status != GoalStatus::SUCCEEDED {
+        return Err(TfError::BufferServer(
+            TF2Error::TRANSFORM_ERROR,
+            format!(
+                "the goal ended with status {} {}",
+                result.status.status, result.status.text
+            ),
+        ));
+    }
+    let error = result.result.error;
+    if error.error != TF2Error::NO_ERROR {
+        return Err(TfError::BufferServer(error.error, error.error_string));
+    }
+    Ok(result.result.transform)
+}
+
+fn rosrust_error(err: rosrust::error::Error) -> TfError {
+    TfError::Rosrust(err.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn result(goal_id: &str, status: u8, error: u8) -> LookupTransformActionResult {
+        let mut result = LookupTransformActionResult::default();
+        result.status.goal_id.id = goal_id.to_string();
+        result.status.status = status;
+        result.result.error.error = error;
+        result.result.transform.child_frame_id = "base_link".to_string();
+        result
+    }
+
+    #[test]
+    fn test_pending_goals() {
+        let pending_goals = Arc::new(PendingGoals::default());
+        pending_goals.insert("goal");
+        assert!(pending_goals
+            .wait("goal", Duration::from_millis(10))
+            .is_none());
+
+        pending_goals.insert("goal");
+        let waiter = {
+            let pending_goals = pending_goals.clone();
+            std::thread::spawn(move || pending_goals.wait("goal", Duration::from_secs(10)))
+        };
+        // The results of other goals are ignored.
+        pending_goals.complete(result("other", GoalStatus::SUCCEEDED, TF2Error::NO_ERROR));
+        pending_goals.complete(result("goal", GoalStatus::SUCCEEDED, TF2Error::NO_ERROR));
+        assert!(waiter.join().unwrap().is_some());
+        assert!(pending_goals.lock_results().is_empty());
+    }
+
+    #[test]
+    fn test_goal_timeout() {
+        let timeout = goal_timeout(Duration::from_millis(1500));
+        assert_eq!((timeout.sec, timeout.nsec), (1, 500_000_000));
+        let timeout = goal_timeout(Duration::MAX);
+        assert_eq!((timeout.sec, timeout.nsec), (i32::MAX, 999_999_999));
+        assert!(timeout.nanos() > 0);
+    }
+
+    #[test]
+    fn test_transform_from_result() {
+        let tf = transform_from_result(result("goal", GoalStatus::SUCCEEDED, TF2Error::NO_ERROR));
+        assert_eq!(tf.unwrap().child_frame_id, "base_link");
+        assert!(matches!(
+            transform_from_result(result(
+                "goal",
+                GoalStatus::SUCCEEDED,
+                TF2Error::EXTRAPOLATION_ERROR
+            )),
+            Err(TfError::BufferServer(TF2Error::EXTRAPOLATION_ERROR, _))
+        ));
+        assert!(matches!(
+            transform_from_result(result("goal", GoalStatus::PREEMPTED, TF2Error::NO_ERROR)),
+            Err(TfError::BufferServer(TF2Error::TRANSFORM_ERROR, _))
+        ));
+    }
+}
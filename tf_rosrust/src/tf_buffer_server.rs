@@ -0,0 +1,389 @@
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError, Weak,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    tf_error::TfError,
+    tf_listener::TfListener,
+    transforms::{
+        actionlib_msgs::{GoalID, GoalStatus, GoalStatusArray},
+        geometry_msgs::TransformStamped,
+        std_msgs::Header,
+        tf2_msgs::{
+            LookupTransformActionFeedback, LookupTransformActionGoal, LookupTransformActionResult,
+            LookupTransformGoal, LookupTransformResult, TF2Error,
+        },
+    },
+};
+
+/// Period of the status messages, like the default of actionlib
+const STATUS_PERIOD: Duration = Duration::from_millis(200);
+const QUEUE_SIZE: usize = 100;
+/// Maximum number of goals answered at the same time, as each of them blocks a thread
+const MAX_ACTIVE_GOALS: usize = 100;
+
+/// Answers `tf2_msgs/LookupTransform` action goals with the transforms of a [`TfListener`], like
+/// `BufferServer` of tf2_ros
+///
+/// Nodes can then look up transforms through a [`BufferClient`](crate::BufferClient) instead of
+/// subscribing to `/tf` themselves. The `buffer_server` binary runs a server in the
+/// `tf2_buffer_server` namespace, like tf2_ros.
+///
+/// ```no_run
+/// use tf_rosrust::{BufferServer, TfListener};
+///
+/// rosrust::init("tf2_buffer_server");
+/// let _server = BufferServer::new(TfListener::new(), "tf2_buffer_server").unwrap();
+/// rosrust::spin();
+/// ```
+///
+/// Each goal is answered once its transform is available or its timeout elapses, or is preempted
+/// when it is canceled. Like in tf2_ros, failed lookups still succeed the goal, with the error in
+/// the result. At most 100 goals are answered at the same time, and further goals are rejected.
+pub struct BufferServer {
+    _shared: Arc<ServerShared>,
+    _subscribers: Vec<rosrust::Subscriber>,
+}
+
+struct ServerShared {
+    listener: TfListener,
+    result_publisher: rosrust::Publisher<LookupTransformActionResult>,
+    status_publisher: rosrust::Publisher<GoalStatusArray>,
+    // Advertised so that actionlib clients consider the server connected, but never used.
+    _feedback_publisher: rosrust::Publisher<LookupTransformActionFeedback>,
+    // Goals which are waiting for their transform
+    active_goals: Mutex<Vec<ActiveGoal>>,
+}
+
+struct ActiveGoal {
+    goal_id: GoalID,
+    // Set when the goal is canceled, to stop waiting for its transform
+    cancelled: Arc<AtomicBool>,
+}
+
+impl BufferServer {
+    /// Answers the goals sent to the action in the given namespace with the transforms of the
+    /// listener
+    pub fn new(listener: TfListener, namespace: &str) -> Result<Self, TfError> {
+        let namespace = namespace.trim_end_matches('/');
+        let shared = Arc::new(ServerShared {
+            listener,
+            result_publisher: rosrust::publish(&format!("{namespace}/result"), QUEUE_SIZE)
+                .map_err(rosrust_error)?,
+            status_publisher: rosrust::publish(&format!("{namespace}/status"), QUEUE_SIZE)
+                .map_err(rosrust_error)?,
+            _feedback_publisher: rosrust::publish(&format!("{namespace}/feedback"), QUEUE_SIZE)
+                .map_err(rosrust_error)?,
+            active_goals: Mutex::new(Vec::new()),
+        });
+
+        let s1 = shared.clone();
+        let goal_subscriber = rosrust::subscribe(
+            &format!("{namespace}/goal"),
+            QUEUE_SIZE,
+            move |goal: LookupTransformActionGoal| s1.start(goal),
+        )
+        .map_err(rosrust_error)?;
+        let s2 = shared.clone();
+        let cancel_subscriber = rosrust::subscribe(
+            &format!("{namespace}/cancel"),
+            QUEUE_SIZE,
+            move |cancel: GoalID| s2.cancel(&cancel),
+        )
+        .map_err(rosrust_error)?;
+
+        let weak = Arc::downgrade(&shared);
+        thread::spawn(move || publish_status(weak));
+
+        Ok(Self {
+            _shared: shared,
+            _subscribers: vec![goal_subscriber, cancel_subscriber],
+        })
+    }
+}
+
+impl ServerShared {
+    /// Looks up the transform of the goal from a new thread, or rejects the goal if too many goals
+    /// are active
+    fn start(self: &Arc<Self>, goal: LookupTransformActionGoal) {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let accepted = {
+            let mut active_goals = self.lock_active_goals();
+            let accepted = active_goals.len() < MAX_ACTIVE_GOALS;
+            if accepted {
+                active_goals.push(ActiveGoal {
+                    goal_id: goal.goal_id.clone(),
+                    cancelled: cancelled.clone(),
+                });
+            }
+            accepted
+        };
+        if !accepted {
+            self.publish_result(goal.goal_id, GoalStatus::REJECTED, Default::default());
+            return;
+        }
+        let shared = self.clone();
+        thread::spawn(move || {
+            let result = lookup_result(lookup(&shared.listener, &goal.goal, &cancelled));
+            shared.finish(goal.goal_id, result);
+        });
+    }
+
+    /// Preempts the canceled goals and stops their lookups
+    fn cancel(&self, cancel: &GoalID) {
+        let canceled: Vec<_> = {
+            let mut active_goals = self.lock_active_goals();
+            let (canceled, active) = active_goals
+                .drain(..)
+                .partition(|goal| cancels(cancel, &goal.goal_id));
+            *active_goals = active;
+            canceled
+        };
+        if canceled.is_empty() {
+            return;
+        }
+        for goal in &canceled {
+            goal.cancelled.store(true, Ordering::Release);
+        }
+        self.listener.wake_waiters();
+        for goal in canceled {
+            self.publish_result(goal.goal_id, GoalStatus::PREEMPTED, Default::default());
+        }
+    }
+
+    /// Publishes the result of the goal, unless it was canceled meanwhile
+    fn finish(&self, goal_id: GoalID, result: LookupTransformResult) {
+        let was_active = {
+            let mut active_goals = self.lock_active_goals();
+            let index = active_goals
+                .iter()
+                .position(|active| active.goal_id == goal_id);
+            index.map(|index| active_goals.remove(index)).is_some()
+        };
+        if was_active {
+            self.publish_result(goal_id, GoalStatus::SUCCEEDED, result);
+        }
+    }
+
+    fn publish_result(&self, goal_id: GoalID, status: u8, result: LookupTransformResult) {
+        let result = LookupTransformActionResult {
+            header: header(),
+            status: GoalStatus {
+                goal_id,
+                status,
+                text: String::new(),
+            },
+            result,
+        };
+        if let Err(err) = self.result_publisher.send(result) {
+            rosrust::ros_warn!("tf_rosrust: failed to publish a LookupTransform result: {err}");
+        }
+    }
+
+    fn lock_active_goals(&self) -> std::sync::MutexGuard<'_, Vec<ActiveGoal>> {
+        self.active_goals
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Publishes the active goals regularly until the server is dropped, as actionlib clients wait
+/// for the status of the server
+fn publish_status(shared: Weak<ServerShared>) {
+    while rosrust::is_ok() {
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        let status_list = shared
+            .lock_active_goals()
+            .iter()
+            .map(|goal| GoalStatus {
+                goal_id: goal.goal_id.clone(),
+                status: GoalStatus::ACTIVE,
+                text: String::new(),
+            })
+            .collect();
+        let _ = shared.status_publisher.send(GoalStatusArray {
+            header: header(),
+            status_list,
+        });
+        drop(shared);
+        thread::sleep(STATUS_PERIOD);
+    }
+}
+
+fn lookup(
+    listener: &TfListener,
+    goal: &LookupTransformGoal,
+    cancelled: &AtomicBool,
+) -> Result<TransformStamped, TfError> {
+    let timeout = Duration::from_nanos(goal.timeout.nanos().max(0) as u64);
+    listener.wait_for(timeout, cancelled, || {
+        if goal.advanced {
+            listener
+                .lookup_transform_with_time_travel(
+                    &goal.target_frame,
+                    goal.target_time,
+                    &goal.source_frame,
+                    goal.source_time,
+                    &goal.fixed_frame,
+                )
+                .map(|transform| relabel_time_travel(transform, goal.target_time))
+        } else {
+            listener.lookup_transform(&goal.target_frame, &goal.source_frame, goal.source_time)
+        }
+    })
+}
+
+/// Labels the result of a time travel lookup like tf2, which expresses it in the target frame at
+/// the target time
+///
+/// [`TfListener::lookup_transform_with_time_travel`] computes the same transform as tf2, but labels
+/// it with the source frame and time.
+fn relabel_time_travel(
+    mut transform: TransformStamped,
+    target_time: rosrust::Time,
+) -> TransformStamped {
+    mem::swap(
+        &mut transform.header.frame_id,
+        &mut transform.child_frame_id,
+    );
+    transform.header.stamp = target_time;
+    transform
+}
+
+fn lookup_result(result: Result<TransformStamped, TfError>) -> LookupTransformResult {
+    match result {
+        Ok(transform) => LookupTransformResult {
+            transform,
+            error: TF2Error {
+                error: TF2Error::NO_ERROR,
+                error_string: String::new(),
+            },
+        },
+        Err(err) => LookupTransformResult {
+            transform: TransformStamped::default(),
+            error: TF2Error {
+                error: tf2_error_code(&err),
+                error_string: err.to_string(),
+            },
+        },
+    }
+}
+
+/// Returns the `tf2_msgs/TF2Error` code of the error
+fn tf2_error_code(err: &TfError) -> u8 {
+    match err {
+        TfError::CouldNotFindTransform(..) | TfError::EmptyTransformChain(..) => {
+            TF2Error::LOOKUP_ERROR
+        }
+        TfError::AttemptedLookupInPast(..) | TfError::AttemptedLookUpInFuture(..) => {
+            TF2Error::EXTRAPOLATION_ERROR
+        }
+        TfError::InvalidArgument(..) => TF2Error::INVALID_ARGUMENT_ERROR,
+        TfError::BufferServer(code, _) => *code,
+        _ => TF2Error::TRANSFORM_ERROR,
+    }
+}
+
+/// Returns whether the cancel request of actionlib applies to the goal
+///
+/// An empty ID cancels all goals, and a non-zero stamp also cancels the goals sent before it.
+fn cancels(cancel: &GoalID, goal_id: &GoalID) -> bool {
+    let all = cancel.id.is_empty() && cancel.stamp == rosrust::Time::new();
+    let before = cancel.stamp != rosrust::Time::new() && goal_id.stamp <= cancel.stamp;
+    all || before || (!cancel.id.is_empty() && cancel.id == goal_id.id)
+}
+
+fn header() -> Header {
+    Header {
+        stamp: rosrust::now(),
+        ..Default::default()
+    }
+}
+
+fn rosrust_error(err: rosrust::error::Error) -> TfError {
+    TfError::Rosrust(err.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{test_utils::transform, tf_buffer::TfBuffer};
+
+    fn goal_id(id: &str, sec: u32) -> GoalID {
+        GoalID {
+            stamp: rosrust::Time { sec, nsec: 0 },
+            id: id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cancels() {
+        // Cancel all goals
+        assert!(cancels(&goal_id("", 0), &goal_id("goal", 10)));
+        // Cancel one goal
+        assert!(cancels(&goal_id("goal", 0), &goal_id("goal", 10)));
+        assert!(!cancels(&goal_id("other", 0), &goal_id("goal", 10)));
+        // Cancel the goals sent before a stamp
+        assert!(cancels(&goal_id("", 10), &goal_id("goal", 10)));
+        assert!(!cancels(&goal_id("", 9), &goal_id("goal", 10)));
+        assert!(cancels(&goal_id("other", 10), &goal_id("goal", 5)));
+    }
+
+    #[test]
+    fn test_advanced_lookup() {
+        let mut buffer = TfBuffer::new();
+        for sec in [1, 2] {
+            let odom_to_base = transform("odom", "base_link", sec, sec.into());
+            buffer.set_transform(&odom_to_base, "", false).unwrap();
+            let base_to_camera = transform("base_link", "camera", sec, 0.5);
+            buffer.set_transform(&base_to_camera, "", false).unwrap();
+        }
+        let listener = TfListener::without_subscribers(buffer);
+        let goal = LookupTransformGoal {
+            target_frame: "camera".to_string(),
+            target_time: rosrust::Time { sec: 2, nsec: 0 },
+            source_frame: "base_link".to_string(),
+            source_time: rosrust::Time { sec: 1, nsec: 0 },
+            fixed_frame: "odom".to_string(),
+            advanced: true,
+            ..Default::default()
+        };
+        let result = lookup_result(lookup(&listener, &goal, &AtomicBool::new(false)));
+        assert_eq!(result.error.error, TF2Error::NO_ERROR);
+        // Like in tf2, the result is expressed in the target frame at the target time.
+        assert_eq!(result.transform.header.frame_id, "camera");
+        assert_eq!(result.transform.child_frame_id, "base_link");
+        assert_eq!(result.transform.header.stamp, goal.target_time);
+        // base_link was at 1 at the source time, and the camera at 2.5 at the target time.
+        assert!((result.transform.transform.translation.x + 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lookup_result() {
+        let result = lookup_result(Ok(TransformStamped::default()));
+        assert_eq!(result.error.error, TF2Error::NO_ERROR);
+
+        let err = TfError::CouldNotFindTransform("a".into(), "b".into(), HashMap::new());
+        let result = lookup_result(Err(err.clone()));
+        assert_eq!(result.error.error, TF2Error::LOOKUP_ERROR);
+        assert_eq!(result.error.error_string, err.to_string());
+        assert_eq!(
+            tf2_error_code(&TfError::InvalidArgument(String::new())),
+            TF2Error::INVALID_ARGUMENT_ERROR
+        );
+        assert_eq!(
+            tf2_error_code(&TfError::Cancelled),
+            TF2Error::TRANSFORM_ERROR
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use crate::{
+    tf_error::TfError,
+    tf_frame_graph::{parse_frames_yaml, FrameDescription},
+    transforms::tf2_msgs::{FrameGraph, FrameGraphReq},
+};
+
+/// Fetches the frame tree of another node through its `tf2_msgs/FrameGraph` service, like
+/// `rqt_tf_tree` does
+///
+/// ```no_run
+/// use tf_rosrust::FrameGraphClient;
+///
+/// rosrust::init("frame_graph_client");
+/// let client = FrameGraphClient::new("/buffer_server/tf2_frames").unwrap();
+/// client
+///     .wait_for_service(std::time::Duration::from_secs(5))
+///     .unwrap();
+/// for frame in client.fetch().unwrap() {
+///     println!("{} -> {}", frame.parent, frame.frame_id);
+/// }
+/// ```
+pub struct FrameGraphClient {
+    service: String,
+    client: rosrust::Client<FrameGraph>,
+}
+
+impl FrameGraphClient {
+    /// Creates a client of the service with the given name
+    pub fn new(service: &str) -> Result<Self, TfError> {
+        let client = rosrust::client::<FrameGraph>(service).map_err(rosrust_error)?;
+        Ok(Self {
+            service: service.to_string(),
+            client,
+        })
+    }
+
+    /// Blocks until the service is advertised or the timeout elapses
+    pub fn wait_for_service(&self, timeout: Duration) -> Result<(), TfError> {
+        rosrust::wait_for_service(&self.service, Some(timeout)).map_err(rosrust_error)
+    }
+
+    /// Calls the service, and returns the frames of the answered tree
+    pub fn fetch(&self) -> Result<Vec<FrameDescription>, TfError> {
+        let response = self
+            .client
+            .req(&FrameGraphReq {})
+            .map_err(|err| TfError::Rosrust(err.to_string()))?
+            .map_err(TfError::Rosrust)?;
+        parse_frames_yaml(&response.frame_yaml)
+    }
+}
+
+fn rosrust_error(err: rosrust::error::Error) -> TfError {
+    TfError::Rosrust(err.to_string())
+}
@@ -1,7 +1,14 @@
+use std::mem;
+
 use rosrust::{Duration, Time};
 
 use crate::{
+    tf_cache_limits::CacheLimits,
+    tf_conflict::{ConflictTolerances, TfConflict},
     tf_error::TfError,
+    tf_graph_node::TfGraphNode,
+    tf_sample_policy::{DuplicatePolicy, LatePolicy, SamplePolicy, SampleStatistics},
+    tf_transform_samples::{SearchResult, TransformSamples},
     transforms::{geometry_msgs::TransformStamped, interpolate, to_transform_stamped},
 };
 
@@ -9,102 +16,452 @@ fn get_nanos(dur: rosrust::Duration) -> i64 {
     i64::from(dur.sec) * 1_000_000_000 + i64::from(dur.nsec)
 }
 
-fn binary_search_time(chain: &[TransformStamped], time: Time) -> Result<usize, usize> {
-    chain.binary_search_by(|element| element.header.stamp.cmp(&time))
-}
-
 #[derive(Clone, Debug)]
 pub(crate) struct TfIndividualTransformChain {
     cache_duration: Duration,
+    max_samples: Option<usize>,
     static_tf: bool,
-    // TODO: Implement a circular buffer. Current method is slow.
-    pub(crate) transform_chain: Vec<TransformStamped>,
+    // Authority of the latest transform
+    authority: String,
+    statistics: SampleStatistics,
+    pub(crate) transform_chain: TransformSamples,
 }
 
 impl TfIndividualTransformChain {
     pub(crate) fn new(static_tf: bool, cache_duration: Duration) -> Self {
         Self {
             cache_duration,
-            transform_chain: Vec::new(),
+            max_samples: None,
+            transform_chain: TransformSamples::default(),
             static_tf,
+            authority: String::new(),
+            statistics: SampleStatistics::default(),
+        }
+    }
+
+    pub(crate) fn with_max_samples(mut self, max_samples: Option<usize>) -> Self {
+        self.max_samples = max_samples;
+        self
+    }
+
+    pub(crate) fn limits(&self) -> CacheLimits {
+        CacheLimits {
+            duration: self.cache_duration,
+            max_samples: self.max_samples,
+        }
+    }
+
+    /// Returns an estimate of the memory used by the chain in bytes
+    pub(crate) fn memory_usage(&self) -> usize {
+        mem::size_of::<Self>() + self.authority.capacity() + self.transform_chain.memory_usage()
+    }
+
+    /// Returns whether a dynamic chain has no sample left within the cache duration before
+    /// `newest_nanos`
+    pub(crate) fn is_expired(&self, newest_nanos: i64) -> bool {
+        if self.static_tf {
+            return false;
+        }
+        match self.newest_stamp() {
+            Some(stamp) => stamp.nanos() < newest_nanos - self.cache_duration.nanos(),
+            None => true,
         }
     }
 
-    fn newest_stamp(&self) -> Option<Time> {
+    pub(crate) fn is_static(&self) -> bool {
+        self.static_tf
+    }
+
+    pub(crate) fn statistics(&self) -> SampleStatistics {
+        self.statistics
+    }
+
+    /// Returns the authority of the latest transform
+    pub(crate) fn authority(&self) -> &str {
+        &self.authority
+    }
+
+    pub(crate) fn oldest_stamp(&self) -> Option<Time> {
+        self.transform_chain.first().map(|x| x.header.stamp)
+    }
+
+    pub(crate) fn newest_stamp(&self) -> Option<Time> {
         self.transform_chain.last().map(|x| x.header.stamp)
     }
 
-    pub(crate) fn add_to_buffer(&mut self, msg: TransformStamped) {
-        let index = binary_search_time(&self.transform_chain, msg.header.stamp)
-            .unwrap_or_else(|index| index);
-        self.transform_chain.insert(index, msg);
+    /// Returns whether a dynamic transform with the stamp would be removed right after insertion
+    fn is_late(&self, stamp: Time) -> bool {
+        !self.static_tf
+            && self.transform_chain.last().is_some_and(|newest| {
+                stamp.nanos() < newest.header.stamp.nanos() - self.cache_duration.nanos()
+            })
+    }
+
+    pub(crate) fn add_to_buffer(
+        &mut self,
+        msg: TransformStamped,
+        authority: &str,
+        policy: SamplePolicy,
+    ) -> Result<(), TfError> {
+        self.statistics.received += 1;
+        if self.is_late(msg.header.stamp) {
+            self.statistics.late += 1;
+            return match policy.late {
+                LatePolicy::Ignore => Ok(()),
+                LatePolicy::Error => Err(TfError::OldData(
+                    msg.header.frame_id,
+                    msg.child_frame_id,
+                    msg.header.stamp,
+                )),
+            };
+        }
+        if self.transform_chain.contains(msg.header.stamp.nanos()) {
+            self.statistics.duplicates += 1;
+            match (self.static_tf, policy.duplicate) {
+                (true, _) | (false, DuplicatePolicy::Replace) => {}
+                (false, DuplicatePolicy::Ignore) => return Ok(()),
+                (false, DuplicatePolicy::Error) => {
+                    return Err(TfError::RepeatedData(
+                        msg.header.frame_id,
+                        msg.child_frame_id,
+                        msg.header.stamp,
+                    ))
+                }
+            }
+        }
+        self.transform_chain.insert(msg);
+        if self.authority != authority {
+            self.authority = authority.to_string();
+        }
 
         if let Some(newest_stamp) = self.newest_stamp() {
             if newest_stamp > Time::from_nanos(0) + self.cache_duration {
                 let time_to_keep = newest_stamp - self.cache_duration;
-                let index =
-                    binary_search_time(&self.transform_chain, time_to_keep).unwrap_or_else(|x| x);
-                self.transform_chain.drain(..index);
+                self.transform_chain.remove_before(time_to_keep.nanos());
             }
         }
+        if let Some(max_samples) = self.max_samples {
+            let excess = self
+                .transform_chain
+                .len()
+                .saturating_sub(max_samples.max(1));
+            self.transform_chain.remove_oldest(excess);
+        }
+        Ok(())
+    }
+
+    /// Returns the conflict of the transform with the stored ones, if any
+    pub(crate) fn find_conflict(
+        &self,
+        msg: &TransformStamped,
+        authority: &str,
+        tolerances: &ConflictTolerances,
+    ) -> Option<TfConflict> {
+        let last = self.transform_chain.last()?;
+        if !self.static_tf {
+            if self.is_late(msg.header.stamp) {
+                return Some(TfConflict::old_data(msg, authority));
+            }
+            if self.transform_chain.contains(msg.header.stamp.nanos()) {
+                return Some(TfConflict::repeated_data(msg, authority));
+            }
+        }
+        TfConflict::competing_authorities(
+            last,
+            &self.authority,
+            msg,
+            authority,
+            self.static_tf,
+            tolerances,
+        )
     }
 
     /// If timestamp is zero, return the latest transform.
     pub(crate) fn get_closest_transform(
         &self,
+        node: &TfGraphNode,
         time: rosrust::Time,
     ) -> Result<TransformStamped, TfError> {
-        if time.nanos() == 0 {
-            return Ok(self.transform_chain.last().unwrap().clone());
-        }
+        let (Some(first), Some(last)) = (self.transform_chain.first(), self.transform_chain.last())
+        else {
+            return Err(TfError::EmptyTransformChain(
+                node.parent.clone(),
+                node.child.clone(),
+            ));
+        };
 
-        if self.static_tf {
-            return Ok(self.transform_chain.last().unwrap().clone());
+        if time.nanos() == 0 || self.static_tf {
+            return Ok(last.clone());
         }
 
-        match binary_search_time(&self.transform_chain, time) {
-            Ok(x) => Ok(self.transform_chain.get(x).unwrap().clone()),
-            Err(x) => {
-                if x == 0 {
-                    return Err(TfError::AttemptedLookupInPast(
-                        time,
-                        Box::new(self.transform_chain.first().unwrap().clone()),
-                    ));
-                }
-                if x >= self.transform_chain.len() {
-                    return Err(TfError::AttemptedLookUpInFuture(
-                        Box::new(self.transform_chain.last().unwrap().clone()),
-                        time,
-                    ));
-                }
-                let tf1 = self.transform_chain.get(x - 1).unwrap().clone().transform;
-                let tf2 = self.transform_chain.get(x).unwrap().clone().transform;
-                let time1 = self.transform_chain.get(x - 1).unwrap().header.stamp;
-                let time2 = self.transform_chain.get(x).unwrap().header.stamp;
-                let header = self.transform_chain.get(x).unwrap().header.clone();
-                let child_frame = self.transform_chain.get(x).unwrap().child_frame_id.clone();
+        match self.transform_chain.search(time.nanos()) {
+            SearchResult::Exact(sample) => Ok(sample.clone()),
+            SearchResult::Between(None, _) => Err(TfError::AttemptedLookupInPast(
+                time,
+                Box::new(first.clone()),
+            )),
+            SearchResult::Between(_, None) => Err(TfError::AttemptedLookUpInFuture(
+                Box::new(last.clone()),
+                time,
+            )),
+            SearchResult::Between(Some(before), Some(after)) => {
+                let time1 = before.header.stamp;
+                let time2 = after.header.stamp;
                 let total_duration = get_nanos(time2 - time1) as f64;
                 let desired_duration = get_nanos(time - time1) as f64;
                 let weight = 1.0 - desired_duration / total_duration;
-                let final_tf = interpolate(tf1, tf2, weight);
-                let ros_msg = to_transform_stamped(final_tf, header.frame_id, child_frame, time);
-                Ok(ros_msg)
+                let final_tf =
+                    interpolate(before.transform.clone(), after.transform.clone(), weight);
+                Ok(to_transform_stamped(
+                    final_tf,
+                    after.header.frame_id.clone(),
+                    after.child_frame_id.clone(),
+                    time,
+                ))
             }
         }
     }
 
     pub(crate) fn has_valid_transform(&self, time: rosrust::Time) -> bool {
-        if self.transform_chain.is_empty() {
+        let (Some(first), Some(last)) = (self.transform_chain.first(), self.transform_chain.last())
+        else {
             return false;
+        };
+
+        self.static_tf
+            || time.nanos() == 0
+            || (time >= first.header.stamp && time <= last.header.stamp)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::transform;
+
+    fn node() -> TfGraphNode {
+        TfGraphNode {
+            child: "base_link".to_string(),
+            parent: "odom".to_string(),
         }
+    }
 
-        if self.static_tf {
-            return true;
+    #[test]
+    fn test_empty_chain() {
+        for static_tf in [false, true] {
+            let chain = TfIndividualTransformChain::new(static_tf, Duration::from_seconds(10));
+            for time in [Time::new(), Time { sec: 1, nsec: 0 }] {
+                assert!(!chain.has_valid_transform(time));
+                assert!(matches!(
+                    chain.get_closest_transform(&node(), time),
+                    Err(TfError::EmptyTransformChain(parent, child))
+                        if parent == "odom" && child == "base_link"
+                ));
+            }
         }
+    }
 
-        let first = self.transform_chain.first().unwrap();
-        let last = self.transform_chain.last().unwrap();
+    #[test]
+    fn test_single_sample_chain() {
+        let mut chain = TfIndividualTransformChain::new(false, Duration::from_seconds(10));
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 5, 1.0),
+                "authority",
+                SamplePolicy::default(),
+            )
+            .unwrap();
+        let exact = chain
+            .get_closest_transform(&node(), Time { sec: 5, nsec: 0 })
+            .unwrap();
+        assert_eq!(exact.transform.translation.x, 1.0);
+        let latest = chain.get_closest_transform(&node(), Time::new()).unwrap();
+        assert_eq!(latest.header.stamp, Time { sec: 5, nsec: 0 });
+        assert!(matches!(
+            chain.get_closest_transform(&node(), Time { sec: 4, nsec: 0 }),
+            Err(TfError::AttemptedLookupInPast(..))
+        ));
+        assert!(matches!(
+            chain.get_closest_transform(&node(), Time { sec: 6, nsec: 0 }),
+            Err(TfError::AttemptedLookUpInFuture(..))
+        ));
+    }
+
+    #[test]
+    fn test_expired_chain() {
+        let mut chain = TfIndividualTransformChain::new(false, Duration::from_seconds(10));
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 1, 1.0),
+                "/publisher1",
+                SamplePolicy::default(),
+            )
+            .unwrap();
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 2, 2.0),
+                "/publisher1",
+                SamplePolicy::default(),
+            )
+            .unwrap();
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 20, 3.0),
+                "/publisher2",
+                SamplePolicy::default(),
+            )
+            .unwrap();
+        assert_eq!(chain.authority(), "/publisher2");
+        // Only the newest sample is left
+        assert_eq!(chain.transform_chain.len(), 1);
+        assert!(!chain.has_valid_transform(Time { sec: 2, nsec: 0 }));
+        assert!(matches!(
+            chain.get_closest_transform(&node(), Time { sec: 2, nsec: 0 }),
+            Err(TfError::AttemptedLookupInPast(..))
+        ));
+        let latest = chain.get_closest_transform(&node(), Time { sec: 20, nsec: 0 });
+        assert_eq!(latest.unwrap().transform.translation.x, 3.0);
+    }
 
-        time.nanos() == 0 || (time >= first.header.stamp && time <= last.header.stamp)
+    #[test]
+    fn test_duplicate_policy() {
+        let policy = |duplicate| SamplePolicy {
+            duplicate,
+            ..Default::default()
+        };
+        let lookup_x = |chain: &TfIndividualTransformChain| {
+            chain
+                .get_closest_transform(&node(), Time { sec: 1, nsec: 0 })
+                .unwrap()
+                .transform
+                .translation
+                .x
+        };
+        let mut chain = TfIndividualTransformChain::new(false, Duration::from_seconds(10));
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 1, 1.0),
+                "authority",
+                policy(DuplicatePolicy::Ignore),
+            )
+            .unwrap();
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 1, 2.0),
+                "authority",
+                policy(DuplicatePolicy::Ignore),
+            )
+            .unwrap();
+        assert_eq!(lookup_x(&chain), 1.0);
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 1, 3.0),
+                "authority",
+                policy(DuplicatePolicy::Replace),
+            )
+            .unwrap();
+        assert_eq!(lookup_x(&chain), 3.0);
+        assert!(matches!(
+            chain.add_to_buffer(
+                transform("odom", "base_link", 1, 4.0),
+                "authority",
+                policy(DuplicatePolicy::Error)
+            ),
+            Err(TfError::RepeatedData(..))
+        ));
+        assert_eq!(lookup_x(&chain), 3.0);
+        assert_eq!(chain.transform_chain.len(), 1);
+        assert_eq!(
+            chain.statistics(),
+            SampleStatistics {
+                received: 4,
+                duplicates: 3,
+                late: 0,
+            }
+        );
+
+        // Static transforms are always replaced
+        let mut chain = TfIndividualTransformChain::new(true, Duration::from_seconds(10));
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 1, 1.0),
+                "authority",
+                policy(DuplicatePolicy::Error),
+            )
+            .unwrap();
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 1, 2.0),
+                "authority",
+                policy(DuplicatePolicy::Error),
+            )
+            .unwrap();
+        assert_eq!(lookup_x(&chain), 2.0);
+    }
+
+    #[test]
+    fn test_late_policy() {
+        let policy = |late| SamplePolicy {
+            late,
+            ..Default::default()
+        };
+        let mut chain = TfIndividualTransformChain::new(false, Duration::from_seconds(10));
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 20, 1.0),
+                "authority",
+                policy(LatePolicy::Ignore),
+            )
+            .unwrap();
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 5, 2.0),
+                "authority",
+                policy(LatePolicy::Ignore),
+            )
+            .unwrap();
+        assert!(matches!(
+            chain.add_to_buffer(
+                transform("odom", "base_link", 5, 2.0),
+                "authority",
+                policy(LatePolicy::Error)
+            ),
+            Err(TfError::OldData(..))
+        ));
+        // Within the cache duration
+        chain
+            .add_to_buffer(
+                transform("odom", "base_link", 10, 3.0),
+                "authority",
+                policy(LatePolicy::Error),
+            )
+            .unwrap();
+        assert_eq!(chain.transform_chain.len(), 2);
+        assert_eq!(
+            chain.statistics(),
+            SampleStatistics {
+                received: 4,
+                duplicates: 0,
+                late: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_max_samples() {
+        let mut chain = TfIndividualTransformChain::new(false, Duration::from_seconds(10))
+            .with_max_samples(Some(2));
+        for sec in 1..=4 {
+            chain
+                .add_to_buffer(
+                    transform("odom", "base_link", sec, 0.0),
+                    "authority",
+                    SamplePolicy::default(),
+                )
+                .unwrap();
+        }
+        assert_eq!(chain.transform_chain.len(), 2);
+        assert_eq!(chain.transform_chain.first().unwrap().header.stamp.sec, 3);
+        assert!(chain.memory_usage() > 2 * mem::size_of::<TransformStamped>());
     }
 }
@@ -1,9 +1,20 @@
-use std::sync::{Arc, RwLock};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
 
 use crate::{
     tf_buffer::TfBuffer,
     tf_error::TfError,
-    transforms::{geometry_msgs::TransformStamped, tf2_msgs::TFMessage},
+    tf_shared_buffer::SharedTfBuffer,
+    transforms::{
+        geometry_msgs::TransformStamped,
+        tf2_msgs::{FrameGraph, FrameGraphRes, TFMessage},
+    },
+    utils::warn,
 };
 
 ///This struct tries to be the same as the C++ version of `TransformListener`. Use this struct to lookup transforms.
@@ -23,40 +34,115 @@ use crate::{
 ///     rate.sleep();
 /// }
 /// ```
-/// Do note that unlike the C++ variant of the TfListener, only one TfListener can be created at a time. Like its C++ counterpart,
-/// it must be scoped to exist through the lifetime of the program. One way to do this is using an `Arc` or `RwLock`.
+/// Like its C++ counterpart, the listener must be scoped to exist through the lifetime of the program. The listener is
+/// cheap to clone, and all clones share the same subscribers and buffer, so that several components can use a single
+/// subscription to `/tf` instead of creating a listener each.
+///
+/// The subscribers are shut down when the last clone is dropped or [`TfListener::shutdown`] is called.
+///
+/// The caller ID of the node which published a transform is recorded as its authority, see
+/// [`TfBuffer::authority`]. When the ROS time jumps backward, e.g. when a bag is played in a loop,
+/// the dynamic transforms are removed from the buffer.
+///
+/// If [`TfListenerBuilder::frame_graph_service`] is set, the listener answers `tf2_msgs/FrameGraph`
+/// requests with [`TfBuffer::all_frames_as_yaml`], so that tools like `rqt_tf_tree` can show its
+/// tree.
+#[derive(Clone)]
 pub struct TfListener {
-    buffer: Arc<RwLock<TfBuffer>>,
-    _static_subscriber: rosrust::Subscriber,
-    _dynamic_subscriber: rosrust::Subscriber,
+    shared: Arc<ListenerShared>,
+    subscribers: Arc<Subscribers>,
+}
+
+/// Shuts down the listener when the last clone is dropped
+struct Subscribers {
+    shared: Arc<ListenerShared>,
+    subscribers: Mutex<Vec<rosrust::Subscriber>>,
+    service: Mutex<Option<rosrust::Service>>,
+}
+
+/// State shared between the listener and the subscriber callbacks
+struct ListenerShared {
+    buffer: Arc<SharedTfBuffer>,
+    is_shutdown: AtomicBool,
+    // Incremented every time new transforms are received.
+    generation: Mutex<u64>,
+    updated: Condvar,
+    // ROS time when transforms were last received, to detect jumps backward in time
+    last_update: Mutex<Option<rosrust::Time>>,
 }
 
 impl TfListener {
     /// Create a new TfListener
+    ///
+    /// # Panics
+    ///
+    /// Panics if rosrust is not initialized or the subscribers could not be created. Use
+    /// [`TfListener::try_new`] to handle the error instead.
+    #[track_caller]
     pub fn new() -> Self {
         Self::new_with_buffer(TfBuffer::new())
     }
 
+    /// Create a new TfListener, returning an error if rosrust is not initialized or the
+    /// subscribers could not be created.
+    pub fn try_new() -> Result<Self, TfError> {
+        Self::try_new_with_buffer(TfBuffer::new())
+    }
+
+    /// Create a new TfListener which stores the received transforms in the given buffer
+    ///
+    /// # Panics
+    ///
+    /// Panics if rosrust is not initialized or the subscribers could not be created. Use
+    /// [`TfListener::try_new_with_buffer`] to handle the error instead.
+    #[track_caller]
     pub fn new_with_buffer(tf_buffer: TfBuffer) -> Self {
-        let buff = RwLock::new(tf_buffer);
-        let arc = Arc::new(buff);
-        let r1 = arc.clone();
-        let _dynamic_subscriber = rosrust::subscribe("tf", 100, move |v: TFMessage| {
-            r1.write().unwrap().handle_incoming_transforms(v, false);
-        })
-        .unwrap();
+        Self::try_new_with_buffer(tf_buffer).unwrap()
+    }
 
-        let r2 = arc.clone();
-        let _static_subscriber = rosrust::subscribe("tf_static", 100, move |v: TFMessage| {
-            r2.write().unwrap().handle_incoming_transforms(v, true);
-        })
-        .unwrap();
+    /// Create a new TfListener which stores the received transforms in the given buffer,
+    /// returning an error if rosrust is not initialized or the subscribers could not be created.
+    pub fn try_new_with_buffer(tf_buffer: TfBuffer) -> Result<Self, TfError> {
+        Self::builder().buffer(tf_buffer).build()
+    }
 
-        TfListener {
-            buffer: arc,
-            _static_subscriber,
-            _dynamic_subscriber,
-        }
+    /// Create a new TfListener which stores the received transforms in the given buffer
+    ///
+    /// The buffer may be shared with other listeners or components.
+    ///
+    /// # Panics
+    ///
+    /// Panics if rosrust is not initialized or the subscribers could not be created. Use
+    /// [`TfListenerBuilder::shared_buffer`] to handle the error instead.
+    #[track_caller]
+    pub fn new_with_shared_buffer(tf_buffer: Arc<SharedTfBuffer>) -> Self {
+        Self::builder().shared_buffer(tf_buffer).build().unwrap()
+    }
+
+    /// Returns a builder to configure the topics and queue size of a TfListener
+    ///
+    /// ```no_run
+    /// use tf_rosrust::TfListener;
+    ///
+    /// rosrust::init("listener");
+    /// let listener = TfListener::builder()
+    ///     .namespace("/robot1")
+    ///     .queue_size(1000)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder() -> TfListenerBuilder {
+        TfListenerBuilder::default()
+    }
+
+    /// Returns a snapshot of the buffer, which is not affected by transforms received later
+    pub fn buffer(&self) -> Arc<TfBuffer> {
+        self.shared.buffer.snapshot()
+    }
+
+    /// Returns the buffer which stores the received transforms
+    pub fn shared_buffer(&self) -> Arc<SharedTfBuffer> {
+        self.shared.buffer.clone()
     }
 
     /// Looks up a transform within the tree at a given time.
@@ -66,7 +152,42 @@ impl TfListener {
         to: &str,
         time: rosrust::Time,
     ) -> Result<TransformStamped, TfError> {
-        self.buffer.read().unwrap().lookup_transform(from, to, time)
+        self.shared.buffer.lookup_transform(from, to, time)
+    }
+
+    /// Blocks until the transform is available or the timeout elapses.
+    ///
+    /// Returns the last lookup error if the timeout elapses, and [`TfError::Cancelled`] if the
+    /// listener is shut down while waiting.
+    pub fn wait_for_transform(
+        &self,
+        from: &str,
+        to: &str,
+        time: rosrust::Time,
+        timeout: Duration,
+    ) -> Result<TransformStamped, TfError> {
+        self.shared.wait_for(timeout, &AtomicBool::new(false), || {
+            self.lookup_transform(from, to, time)
+        })
+    }
+
+    /// Blocks until `lookup` succeeds or the timeout elapses, retrying whenever transforms are
+    /// received
+    ///
+    /// Returns [`TfError::Cancelled`] once `cancelled` is set and [`TfListener::wake_waiters`] is
+    /// called.
+    pub(crate) fn wait_for(
+        &self,
+        timeout: Duration,
+        cancelled: &AtomicBool,
+        lookup: impl FnMut() -> Result<TransformStamped, TfError>,
+    ) -> Result<TransformStamped, TfError> {
+        self.shared.wait_for(timeout, cancelled, lookup)
+    }
+
+    /// Wakes up all threads waiting for transforms, so that they check whether they are cancelled
+    pub(crate) fn wake_waiters(&self) {
+        self.shared.wake_waiters();
     }
 
     /// Looks up a transform within the tree at a given time.
@@ -78,11 +199,164 @@ impl TfListener {
         time2: rosrust::Time,
         fixed_frame: &str,
     ) -> Result<TransformStamped, TfError> {
-        self.buffer
-            .read()
-            .unwrap()
+        self.shared
+            .buffer
+            .snapshot()
             .lookup_transform_with_time_travel(from, time1, to, time2, fixed_frame)
     }
+
+    /// Unsubscribes from the topics and wakes up all threads waiting for transforms.
+    ///
+    /// Once this returns, the buffer is no longer updated. This affects all clones of the
+    /// listener, and is also called when the last clone is dropped.
+    pub fn shutdown(&self) {
+        self.subscribers.shutdown();
+    }
+
+    /// Returns `true` if [`TfListener::shutdown`] was called
+    pub fn is_shutdown(&self) -> bool {
+        self.shared.is_shutdown.load(Ordering::Acquire)
+    }
+
+    /// Creates a listener of the buffer which does not subscribe to any topic
+    #[cfg(test)]
+    pub(crate) fn without_subscribers(tf_buffer: TfBuffer) -> Self {
+        let shared = Arc::new(ListenerShared::new(Arc::new(tf_buffer.into())));
+        TfListener {
+            shared: shared.clone(),
+            subscribers: Arc::new(Subscribers {
+                shared,
+                subscribers: Mutex::new(Vec::new()),
+                service: Mutex::new(None),
+            }),
+        }
+    }
+}
+
+impl Subscribers {
+    fn shutdown(&self) {
+        self.shared.shutdown();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.service
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+impl Drop for Subscribers {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl ListenerShared {
+    fn new(buffer: Arc<SharedTfBuffer>) -> Self {
+        Self {
+            buffer,
+            is_shutdown: AtomicBool::new(false),
+            generation: Mutex::new(0),
+            updated: Condvar::new(),
+            last_update: Mutex::new(None),
+        }
+    }
+
+    fn handle_incoming_transforms(
+        &self,
+        transforms: TFMessage,
+        authority: &str,
+        now: impl FnOnce() -> rosrust::Time,
+        static_tf: bool,
+    ) {
+        let inserted = self.buffer.update(|buffer| {
+            // Checked while holding the update lock, so that no callback updates the buffer after
+            // `shutdown` returned.
+            if self.is_shutdown.load(Ordering::Acquire) {
+                return false;
+            }
+            // Read while holding the update lock, so that the updates see increasing times.
+            if let Some(jump) = self.detect_time_jump(now()) {
+                warn(format!(
+                    "detected jump back in time of {}s, clearing the buffer",
+                    jump.seconds()
+                ));
+                buffer.clear();
+            }
+            buffer.handle_incoming_transforms(transforms, authority, static_tf);
+            true
+        });
+        if !inserted {
+            return;
+        }
+        *self.lock_generation() += 1;
+        self.updated.notify_all();
+    }
+
+    /// Returns the length of the jump if the time moved backward by more than [`MIN_TIME_JUMP`]
+    /// since the last update
+    fn detect_time_jump(&self, now: rosrust::Time) -> Option<rosrust::Duration> {
+        let mut last_update = self
+            .last_update
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let jump = last_update
+            .map(|last_update| last_update - now)
+            .filter(|jump| *jump > MIN_TIME_JUMP);
+        *last_update = Some(now);
+        jump
+    }
+
+    fn shutdown(&self) {
+        {
+            let _update = self.buffer.lock_updates();
+            self.is_shutdown.store(true, Ordering::Release);
+        }
+        self.wake_waiters();
+    }
+
+    fn wake_waiters(&self) {
+        let _generation = self.lock_generation();
+        self.updated.notify_all();
+    }
+
+    fn wait_for(
+        &self,
+        timeout: Duration,
+        cancelled: &AtomicBool,
+        mut lookup: impl FnMut() -> Result<TransformStamped, TfError>,
+    ) -> Result<TransformStamped, TfError> {
+        let deadline = Instant::now() + timeout;
+        let mut generation = self.lock_generation();
+        loop {
+            if self.is_shutdown.load(Ordering::Acquire) || cancelled.load(Ordering::Acquire) {
+                return Err(TfError::Cancelled);
+            }
+            let result = lookup();
+            let now = Instant::now();
+            if result.is_ok() || now >= deadline {
+                return result;
+            }
+            let current = *generation;
+            generation = self
+                .updated
+                .wait_timeout_while(generation, deadline - now, |generation| {
+                    *generation == current
+                        && !self.is_shutdown.load(Ordering::Acquire)
+                        && !cancelled.load(Ordering::Acquire)
+                })
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    fn lock_generation(&self) -> std::sync::MutexGuard<'_, u64> {
+        self.generation
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 impl Default for TfListener {
@@ -90,3 +364,315 @@ impl Default for TfListener {
         TfListener::new()
     }
 }
+
+/// Builder of [`TfListener`]
+#[derive(Debug)]
+#[must_use]
+pub struct TfListenerBuilder {
+    buffer: Option<Arc<SharedTfBuffer>>,
+    dynamic_topic: String,
+    static_topic: String,
+    queue_size: usize,
+    frame_graph_service: Option<String>,
+}
+
+impl Default for TfListenerBuilder {
+    fn default() -> Self {
+        Self {
+            buffer: None,
+            dynamic_topic: DEFAULT_DYNAMIC_TOPIC.to_string(),
+            static_topic: DEFAULT_STATIC_TOPIC.to_string(),
+            queue_size: DEFAULT_QUEUE_SIZE,
+            frame_graph_service: None,
+        }
+    }
+}
+
+impl TfListenerBuilder {
+    /// Sets the buffer which stores the received transforms
+    pub fn buffer(self, tf_buffer: TfBuffer) -> Self {
+        self.shared_buffer(Arc::new(SharedTfBuffer::new(tf_buffer)))
+    }
+
+    /// Sets a buffer which may be shared with other listeners or components
+    pub fn shared_buffer(mut self, tf_buffer: Arc<SharedTfBuffer>) -> Self {
+        self.buffer = Some(tf_buffer);
+        self
+    }
+
+    /// Sets the topics of the dynamic and static transforms. The defaults are `tf` and `tf_static`,
+    /// which are resolved in the namespace of the node like in tf. This differs from the
+    /// [`TfBroadcasterBuilder`](crate::TfBroadcasterBuilder), which publishes to the global `/tf`
+    /// by default, so a listener in another namespace needs [`Self::namespace`] with `"/"` to
+    /// receive its transforms.
+    pub fn topics(
+        mut self,
+        dynamic_topic: impl Into<String>,
+        static_topic: impl Into<String>,
+    ) -> Self {
+        self.dynamic_topic = dynamic_topic.into();
+        self.static_topic = static_topic.into();
+        self
+    }
+
+    /// Subscribes to `tf` and `tf_static` in the given namespace. Use `"/"` for the global `/tf`
+    /// and `/tf_static`, regardless of the namespace of the node.
+    pub fn namespace(self, namespace: &str) -> Self {
+        let (dynamic_topic, static_topic) = namespaced_topics(namespace);
+        self.topics(dynamic_topic, static_topic)
+    }
+
+    /// Sets the queue size of both subscribers. The default is 100.
+    pub fn queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size;
+        self
+    }
+
+    /// Advertises a `tf2_msgs/FrameGraph` service with the given name, which answers the frames of
+    /// the buffer in YAML. tf2 uses `~tf2_frames`. By default, no service is advertised.
+    pub fn frame_graph_service(mut self, service: impl Into<String>) -> Self {
+        self.frame_graph_service = Some(service.into());
+        self
+    }
+
+    /// Creates the TfListener and subscribes to the topics
+    pub fn build(self) -> Result<TfListener, TfError> {
+        if !rosrust::is_initialized() {
+            return Err(TfError::Rosrust("rosrust is not initialized".to_string()));
+        }
+        let shared = Arc::new(ListenerShared::new(self.buffer.unwrap_or_default()));
+        let r1 = shared.clone();
+        let dynamic_subscriber = rosrust::subscribe_with_ids(
+            &self.dynamic_topic,
+            self.queue_size,
+            move |v: TFMessage, caller_id: &str| {
+                r1.handle_incoming_transforms(v, caller_id, rosrust::now, false);
+            },
+        )
+        .map_err(|err| TfError::Rosrust(err.to_string()))?;
+
+        let r2 = shared.clone();
+        let static_subscriber = rosrust::subscribe_with_ids(
+            &self.static_topic,
+            self.queue_size,
+            move |v: TFMessage, caller_id: &str| {
+                r2.handle_incoming_transforms(v, caller_id, rosrust::now, true);
+            },
+        )
+        .map_err(|err| TfError::Rosrust(err.to_string()))?;
+
+        let service = self
+            .frame_graph_service
+            .map(|name| {
+                let r3 = shared.clone();
+                rosrust::service::<FrameGraph, _>(&name, move |_| {
+                    Ok(FrameGraphRes {
+                        frame_yaml: r3.buffer.snapshot().all_frames_as_yaml(),
+                    })
+                })
+            })
+            .transpose()
+            .map_err(|err| TfError::Rosrust(err.to_string()))?;
+
+        Ok(TfListener {
+            shared: shared.clone(),
+            subscribers: Arc::new(Subscribers {
+                shared,
+                subscribers: Mutex::new(vec![dynamic_subscriber, static_subscriber]),
+                service: Mutex::new(service),
+            }),
+        })
+    }
+}
+
+const DEFAULT_DYNAMIC_TOPIC: &str = "tf";
+const DEFAULT_STATIC_TOPIC: &str = "tf_static";
+const DEFAULT_QUEUE_SIZE: usize = 100;
+/// Smaller backward steps of the time are not treated as jumps, as they are caused by callbacks
+/// reading the time concurrently or by clock adjustments
+const MIN_TIME_JUMP: rosrust::Duration = rosrust::Duration {
+    sec: 0,
+    nsec: 100_000_000,
+};
+
+/// Returns the `tf` and `tf_static` topics in the given namespace
+pub(crate) fn namespaced_topics(namespace: &str) -> (String, String) {
+    let namespace = namespace.trim_end_matches('/');
+    (format!("{namespace}/tf"), format!("{namespace}/tf_static"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::transform;
+
+    fn message(parent: &str, child: &str) -> TFMessage {
+        TFMessage {
+            transforms: vec![transform(parent, child, 0, 0.0)],
+        }
+    }
+
+    fn lookup(shared: &ListenerShared) -> Result<TransformStamped, TfError> {
+        shared
+            .buffer
+            .lookup_transform("odom", "base_link", rosrust::Time::new())
+    }
+
+    #[test]
+    fn test_wait_for_transform() {
+        let shared = Arc::new(ListenerShared::new(Arc::default()));
+        let not_cancelled = AtomicBool::new(false);
+        let result = shared.wait_for(Duration::from_millis(10), &not_cancelled, || {
+            lookup(&shared)
+        });
+        assert!(matches!(result, Err(TfError::CouldNotFindTransform(..))));
+
+        let waiter = {
+            let shared = shared.clone();
+            std::thread::spawn(move || {
+                shared.wait_for(Duration::from_secs(10), &AtomicBool::new(false), || {
+                    lookup(&shared)
+                })
+            })
+        };
+        shared.handle_incoming_transforms(
+            message("odom", "base_link"),
+            "/publisher",
+            rosrust::Time::new,
+            false,
+        );
+        assert!(waiter.join().unwrap().is_ok());
+        assert_eq!(
+            shared.buffer.snapshot().authority("odom", "base_link"),
+            Some("/publisher")
+        );
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let shared = Arc::new(ListenerShared::new(Arc::default()));
+        let waiter = {
+            let shared = shared.clone();
+            std::thread::spawn(move || {
+                shared.wait_for(Duration::from_secs(10), &AtomicBool::new(false), || {
+                    lookup(&shared)
+                })
+            })
+        };
+        let start = Instant::now();
+        shared.shutdown();
+        assert!(matches!(waiter.join().unwrap(), Err(TfError::Cancelled)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // Callbacks which run after the shutdown do not touch the buffer.
+        shared.handle_incoming_transforms(
+            message("odom", "base_link"),
+            "/publisher",
+            rosrust::Time::new,
+            false,
+        );
+        assert!(lookup(&shared).is_err());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let shared = Arc::new(ListenerShared::new(Arc::default()));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let waiter = {
+            let shared = shared.clone();
+            let cancelled = cancelled.clone();
+            std::thread::spawn(move || {
+                shared.wait_for(Duration::from_secs(10), &cancelled, || lookup(&shared))
+            })
+        };
+        let start = Instant::now();
+        cancelled.store(true, Ordering::Release);
+        shared.wake_waiters();
+        assert!(matches!(waiter.join().unwrap(), Err(TfError::Cancelled)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(!shared.is_shutdown.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_time_jump() {
+        let shared = ListenerShared::new(Arc::default());
+        const SEC: i64 = 1_000_000_000;
+        let insert = |parent: &str, child: &str, nanos: i64, static_tf: bool| {
+            let now = || rosrust::Time::from_nanos(nanos);
+            shared.handle_incoming_transforms(message(parent, child), "/publisher", now, static_tf);
+        };
+        insert("map", "odom", 10 * SEC, true);
+        insert("odom", "base_link", 10 * SEC, false);
+        insert("odom", "base_link", 11 * SEC, false);
+        // Small backward steps are ignored.
+        insert("base_link", "laser", 11 * SEC - 50_000_000, false);
+        assert!(lookup(&shared).is_ok());
+
+        // The dynamic transforms are removed before inserting the new ones
+        insert("base_link", "camera", SEC, false);
+        assert!(lookup(&shared).is_err());
+        let buffer = shared.buffer.snapshot();
+        assert!(buffer
+            .lookup_transform("map", "odom", rosrust::Time::new())
+            .is_ok());
+        assert!(buffer
+            .lookup_transform("base_link", "camera", rosrust::Time::new())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_clone_shares_buffer() {
+        let buffer = Arc::new(SharedTfBuffer::default());
+        let shared = Arc::new(ListenerShared::new(buffer.clone()));
+        let listener = TfListener {
+            shared: shared.clone(),
+            subscribers: Arc::new(Subscribers {
+                shared: shared.clone(),
+                subscribers: Mutex::default(),
+                service: Mutex::default(),
+            }),
+        };
+        let other = listener.clone();
+        shared.handle_incoming_transforms(
+            message("odom", "base_link"),
+            "/publisher",
+            rosrust::Time::new,
+            false,
+        );
+        assert!(other
+            .buffer()
+            .lookup_transform("odom", "base_link", rosrust::Time::new())
+            .is_ok());
+        assert!(Arc::ptr_eq(&other.shared_buffer(), &buffer));
+
+        // Dropping a clone keeps the listener running.
+        drop(other);
+        assert!(!listener.is_shutdown());
+        drop(listener);
+        assert!(shared.is_shutdown.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_namespaced_topics() {
+        assert_eq!(
+            namespaced_topics("/robot1"),
+            ("/robot1/tf".to_string(), "/robot1/tf_static".to_string())
+        );
+        assert_eq!(
+            namespaced_topics("robot1/"),
+            ("robot1/tf".to_string(), "robot1/tf_static".to_string())
+        );
+        assert_eq!(
+            namespaced_topics("/"),
+            ("/tf".to_string(), "/tf_static".to_string())
+        );
+    }
+
+    #[test]
+    fn test_default_topics() {
+        // The broadcaster publishes to the global `/tf` by default, see `TfBroadcasterBuilder`.
+        let builder = TfListenerBuilder::default();
+        assert_eq!(builder.dynamic_topic, "tf");
+        assert_eq!(builder.static_topic, "tf_static");
+    }
+}
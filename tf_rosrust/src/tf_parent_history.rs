@@ -0,0 +1,183 @@
+use rosrust::Time;
+
+/// A time interval during which a frame had the same parent in a [`TfBuffer`](crate::TfBuffer)
+///
+/// Each interval but the first one of a frame starts with a re-parenting of the frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParentInterval {
+    /// Parent frame ID
+    pub parent: String,
+    /// Authority of the transform which started the interval
+    pub authority: String,
+    /// Stamp of the first transform from the parent
+    pub start: Time,
+    /// Start of the next interval, or `None` if the parent is the current one
+    pub end: Option<Time>,
+}
+
+#[derive(Clone, Debug)]
+struct ParentEntry {
+    parent: String,
+    authority: String,
+    start_nanos: i64,
+}
+
+/// Parents of a frame over time, sorted by start stamp
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ParentHistory {
+    entries: Vec<ParentEntry>,
+}
+
+impl ParentHistory {
+    /// Records that the frame had the parent at the stamp. Returns the previous parent at this
+    /// stamp if the frame was re-parented.
+    pub(crate) fn insert(
+        &mut self,
+        parent: &str,
+        authority: &str,
+        stamp_nanos: i64,
+    ) -> Option<String> {
+        let index = self
+            .entries
+            .partition_point(|entry| entry.start_nanos <= stamp_nanos);
+        let previous = index.checked_sub(1).map(|index| &self.entries[index]);
+        if previous.is_some_and(|previous| previous.parent == parent) {
+            return None;
+        }
+        let previous_parent = previous.map(|previous| previous.parent.clone());
+        self.entries.insert(
+            index,
+            ParentEntry {
+                parent: parent.to_string(),
+                authority: authority.to_string(),
+                start_nanos: stamp_nanos,
+            },
+        );
+        // The next interval is merged if it has the same parent, and the previous one is dropped
+        // if it would be empty.
+        if self
+            .entries
+            .get(index + 1)
+            .is_some_and(|next| next.parent == parent)
+        {
+            self.entries.remove(index + 1);
+        }
+        if index > 0 && self.entries[index - 1].start_nanos == stamp_nanos {
+            self.entries.remove(index - 1);
+        }
+        previous_parent
+    }
+
+    /// Returns the parent at the stamp, or the current parent if the stamp is zero
+    ///
+    /// The first parent is also used before the first stamp.
+    pub(crate) fn parent_at(&self, stamp_nanos: i64) -> Option<&str> {
+        let index = if stamp_nanos == 0 {
+            self.entries.len()
+        } else {
+            self.entries
+                .partition_point(|entry| entry.start_nanos <= stamp_nanos)
+        };
+        self.entries
+            .get(index.saturating_sub(1))
+            .map(|entry| entry.parent.as_str())
+    }
+
+    pub(crate) fn current_parent(&self) -> Option<&str> {
+        self.parent_at(0)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Keeps only the intervals of the parents for which `keep` returns `true`
+    pub(crate) fn retain_parents(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.entries.retain(|entry| keep(&entry.parent));
+        self.entries
+            .dedup_by(|entry, previous| entry.parent == previous.parent);
+    }
+
+    /// Removes the intervals which ended before the stamp
+    pub(crate) fn remove_before(&mut self, stamp_nanos: i64) {
+        let ended = self
+            .entries
+            .iter()
+            .skip(1)
+            .take_while(|entry| entry.start_nanos <= stamp_nanos)
+            .count();
+        self.entries.drain(..ended);
+    }
+
+    pub(crate) fn intervals(&self) -> Vec<ParentInterval> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| ParentInterval {
+                parent: entry.parent.clone(),
+                authority: entry.authority.clone(),
+                start: Time::from_nanos(entry.start_nanos),
+                end: self
+                    .entries
+                    .get(index + 1)
+                    .map(|next| Time::from_nanos(next.start_nanos)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SEC: i64 = 1_000_000_000;
+
+    #[test]
+    fn test_parent_history() {
+        let mut history = ParentHistory::default();
+        assert_eq!(history.insert("table", "/node1", SEC), None);
+        assert_eq!(history.insert("table", "/node1", 2 * SEC), None);
+        assert_eq!(
+            history.insert("gripper", "/node2", 5 * SEC),
+            Some("table".to_string())
+        );
+        // A late transform from the previous parent does not change anything
+        assert_eq!(history.insert("table", "/node1", 4 * SEC), None);
+        assert_eq!(history.parent_at(SEC / 2), Some("table"));
+        assert_eq!(history.parent_at(4 * SEC), Some("table"));
+        assert_eq!(history.parent_at(5 * SEC), Some("gripper"));
+        assert_eq!(history.parent_at(0), Some("gripper"));
+
+        assert_eq!(
+            history.intervals(),
+            [
+                ParentInterval {
+                    parent: "table".to_string(),
+                    authority: "/node1".to_string(),
+                    start: Time { sec: 1, nsec: 0 },
+                    end: Some(Time { sec: 5, nsec: 0 }),
+                },
+                ParentInterval {
+                    parent: "gripper".to_string(),
+                    authority: "/node2".to_string(),
+                    start: Time { sec: 5, nsec: 0 },
+                    end: None,
+                },
+            ]
+        );
+
+        history.remove_before(5 * SEC);
+        assert_eq!(history.parent_at(SEC), Some("gripper"));
+        history.retain_parents(|parent| parent != "gripper");
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn test_static_reparenting() {
+        let mut history = ParentHistory::default();
+        history.insert("table", "/node1", 0);
+        history.insert("gripper", "/node1", 0);
+        assert_eq!(history.intervals().len(), 1);
+        assert_eq!(history.current_parent(), Some("gripper"));
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex, PoisonError,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    tf_broadcaster::TfBroadcaster, tf_error::TfError, transforms::geometry_msgs::TransformStamped,
+};
+
+/// Broadcast a set of transforms at a fixed rate from a background thread
+///
+/// Every transform is restamped with `rosrust::now()` right before it is sent. The set of
+/// transforms can be updated at any time, either directly or through a
+/// [`PeriodicTfBroadcasterHandle`]. The background thread is stopped when the broadcaster is
+/// dropped.
+///
+/// Example usage:
+///
+/// ```no_run
+/// use tf_rosrust::{PeriodicTfBroadcaster, TransformStamped};
+///
+/// rosrust::init("periodic_broadcaster");
+/// let broadcaster = PeriodicTfBroadcaster::new(50.0).unwrap();
+/// let handle = broadcaster.handle();
+///
+/// let mut tf = TransformStamped::default();
+/// tf.header.frame_id = "odom".to_string();
+/// tf.child_frame_id = "base_link".to_string();
+/// tf.transform.rotation.w = 1.0;
+///
+/// let rate = rosrust::rate(10.0);
+/// while rosrust::is_ok() {
+///     tf.transform.translation.x += 0.01;
+///     handle.set_transform(tf.clone());
+///     rate.sleep();
+/// }
+/// ```
+pub struct PeriodicTfBroadcaster {
+    handle: PeriodicTfBroadcasterHandle,
+    stop_sender: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// A cheaply cloneable handle to update the transforms of a [`PeriodicTfBroadcaster`]
+#[derive(Clone, Debug, Default)]
+pub struct PeriodicTfBroadcasterHandle {
+    transforms: Arc<Mutex<BTreeMap<String, TransformStamped>>>,
+}
+
+impl PeriodicTfBroadcaster {
+    /// Create a new PeriodicTfBroadcaster publishing at `rate` Hz
+    pub fn new(rate: f64) -> Result<Self, TfError> {
+        Self::new_with_broadcaster(TfBroadcaster::try_new()?, rate)
+    }
+
+    /// Create a new PeriodicTfBroadcaster publishing through the given broadcaster at `rate` Hz
+    pub fn new_with_broadcaster(broadcaster: TfBroadcaster, rate: f64) -> Result<Self, TfError> {
+        let period = period(rate)?;
+        let handle = PeriodicTfBroadcasterHandle::default();
+        let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+
+        let thread_handle = handle.clone();
+        let thread = std::thread::Builder::new()
+            .name("tf_periodic_broadcaster".to_string())
+            .spawn(move || {
+                let mut deadline = Instant::now() + period;
+                // Dropping the sender disconnects the channel and ends the loop.
+                while let Err(RecvTimeoutError::Timeout) =
+                    stop_receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                {
+                    if !rosrust::is_ok() {
+                        break;
+                    }
+                    let transforms = thread_handle.stamped_transforms(rosrust::now());
+                    if let Err(err) = broadcaster.send_transforms(transforms) {
+                        rosrust::ros_warn!("tf_rosrust: failed to broadcast transforms: {err}");
+                    }
+                    deadline = next_deadline(deadline, period, Instant::now());
+                }
+            })
+            .map_err(|err| TfError::Rosrust(err.to_string()))?;
+
+        Ok(Self {
+            handle,
+            stop_sender: Some(stop_sender),
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns a handle which can update the broadcast transforms from other threads
+    pub fn handle(&self) -> PeriodicTfBroadcasterHandle {
+        self.handle.clone()
+    }
+
+    /// Adds the transform to the broadcast set, replacing the one with the same child frame
+    pub fn set_transform(&self, tf: TransformStamped) {
+        self.handle.set_transform(tf);
+    }
+
+    /// Removes the transform of `child_frame_id` from the broadcast set
+    pub fn remove_transform(&self, child_frame_id: &str) -> Option<TransformStamped> {
+        self.handle.remove_transform(child_frame_id)
+    }
+}
+
+impl Drop for PeriodicTfBroadcaster {
+    fn drop(&mut self) {
+        drop(self.stop_sender.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl PeriodicTfBroadcasterHandle {
+    /// Adds the transform to the broadcast set, replacing the one with the same child frame
+    pub fn set_transform(&self, tf: TransformStamped) {
+        self.lock().insert(tf.child_frame_id.clone(), tf);
+    }
+
+    /// Adds all transforms to the broadcast set
+    pub fn set_transforms(&self, transforms: impl IntoIterator<Item = TransformStamped>) {
+        let mut current = self.lock();
+        for tf in transforms {
+            current.insert(tf.child_frame_id.clone(), tf);
+        }
+    }
+
+    /// Removes the transform of `child_frame_id` from the broadcast set
+    pub fn remove_transform(&self, child_frame_id: &str) -> Option<TransformStamped> {
+        self.lock().remove(child_frame_id)
+    }
+
+    /// Removes all transforms from the broadcast set
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Returns the transforms currently in the broadcast set
+    pub fn transforms(&self) -> Vec<TransformStamped> {
+        self.lock().values().cloned().collect()
+    }
+
+    fn stamped_transforms(&self, stamp: rosrust::Time) -> Vec<TransformStamped> {
+        self.lock()
+            .values()
+            .map(|tf| {
+                let mut tf = tf.clone();
+                tf.header.stamp = stamp;
+                tf
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, TransformStamped>> {
+        // The map is always left in a valid state, so it is fine to ignore poisoning.
+        self.transforms
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Returns the period of the rate, which must be positive and have a non-zero period
+fn period(rate: f64) -> Result<Duration, TfError> {
+    Duration::try_from_secs_f64(1.0 / rate)
+        .ok()
+        .filter(|period| !period.is_zero())
+        .ok_or_else(|| {
+            TfError::InvalidArgument(format!(
+                "rate must be a positive number with a non-zero period, but got {rate}"
+            ))
+        })
+}
+
+/// Returns the deadline of the next cycle, which keeps the rate independent of the time spent
+/// sending. The cycles which were missed entirely are skipped.
+fn next_deadline(deadline: Instant, period: Duration, now: Instant) -> Instant {
+    let next = deadline + period;
+    if next > now {
+        next
+    } else {
+        now + period
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::transform;
+
+    #[test]
+    fn test_handle_updates() {
+        let handle = PeriodicTfBroadcasterHandle::default();
+        let other = handle.clone();
+        handle.set_transform(transform("odom", "base_link", 0, 1.0));
+        other.set_transforms([
+            transform("odom", "base_link", 0, 2.0),
+            transform("base_link", "camera", 0, 0.5),
+        ]);
+        let transforms = handle.transforms();
+        assert_eq!(transforms.len(), 2);
+        assert_eq!(transforms[0].child_frame_id, "base_link");
+        assert_eq!(transforms[0].transform.translation.x, 2.0);
+
+        assert!(other.remove_transform("camera").is_some());
+        assert!(other.remove_transform("camera").is_none());
+        assert_eq!(handle.transforms().len(), 1);
+
+        handle.clear();
+        assert!(other.transforms().is_empty());
+    }
+
+    #[test]
+    fn test_restamp() {
+        let handle = PeriodicTfBroadcasterHandle::default();
+        handle.set_transform(transform("odom", "base_link", 0, 1.0));
+        let stamp = rosrust::Time { sec: 5, nsec: 10 };
+        let transforms = handle.stamped_transforms(stamp);
+        assert_eq!(transforms[0].header.stamp, stamp);
+        // The stored transform keeps its original stamp.
+        assert_eq!(handle.transforms()[0].header.stamp, rosrust::Time::new());
+    }
+
+    #[test]
+    fn test_period() {
+        assert_eq!(period(10.0).unwrap(), Duration::from_millis(100));
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(period(rate), Err(TfError::InvalidArgument(_))));
+        }
+        // The period of tiny rates overflows, and the one of huge rates rounds to zero.
+        assert!(matches!(period(1e-310), Err(TfError::InvalidArgument(_))));
+        assert!(matches!(period(1e300), Err(TfError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_next_deadline() {
+        let start = Instant::now();
+        let period = Duration::from_millis(100);
+        // The time spent sending does not shift the next deadline.
+        let now = start + Duration::from_millis(30);
+        assert_eq!(next_deadline(start, period, now), start + period);
+        // Missed cycles are skipped.
+        let now = start + Duration::from_millis(250);
+        assert_eq!(next_deadline(start, period, now), now + period);
+    }
+}
@@ -0,0 +1,378 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+
+use rosrust::{Duration, Time};
+
+use crate::{
+    tf_broadcaster::TfBroadcaster,
+    tf_error::TfError,
+    transforms::{geometry_msgs::TransformStamped, isometry_from_transform},
+};
+
+/// Thresholds used by [`ThresholdTfBroadcaster`] to decide whether a transform is sent
+#[derive(Clone, Debug, PartialEq)]
+pub struct BroadcastThreshold {
+    /// Minimum change of the translation in meters
+    pub translation: f64,
+    /// Minimum change of the rotation in radians
+    pub rotation: f64,
+    /// A transform is always sent if the last one of the same frame was sent at least this long ago
+    pub keep_alive: Duration,
+}
+
+impl Default for BroadcastThreshold {
+    fn default() -> Self {
+        Self {
+            translation: 0.001,
+            rotation: 0.001,
+            keep_alive: Duration::from_seconds(1),
+        }
+    }
+}
+
+/// Per-frame statistics of a [`ThresholdTfBroadcaster`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BroadcastStatistics {
+    /// Number of transforms which were sent
+    pub sent: u64,
+    /// Number of transforms which were suppressed because they did not change enough
+    pub suppressed: u64,
+}
+
+impl BroadcastStatistics {
+    /// Ratio of suppressed transforms to all transforms given to the broadcaster
+    pub fn suppressed_ratio(&self) -> f64 {
+        let total = self.sent + self.suppressed;
+        if total == 0 {
+            0.0
+        } else {
+            self.suppressed as f64 / total as f64
+        }
+    }
+}
+
+/// Broadcast tf messages only when they changed significantly
+///
+/// A transform is suppressed unless its translation or rotation changed past the
+/// [`BroadcastThreshold`] since the last transform sent for the same child frame, its parent frame
+/// changed, or the keep-alive period has passed since it was sent, as measured by the ROS clock.
+///
+/// Example usage:
+///
+/// ```no_run
+/// use tf_rosrust::{BroadcastThreshold, ThresholdTfBroadcaster, TransformStamped};
+///
+/// rosrust::init("threshold_broadcaster");
+/// let broadcaster = ThresholdTfBroadcaster::new(BroadcastThreshold::default()).unwrap();
+///
+/// let rate = rosrust::rate(500.0);
+/// let mut tf = TransformStamped::default();
+/// tf.header.frame_id = "base_link".to_string();
+/// tf.child_frame_id = "link1".to_string();
+/// tf.transform.rotation.w = 1.0;
+/// while rosrust::is_ok() {
+///     tf.header.stamp = rosrust::now();
+///     broadcaster.send_transform(tf.clone()).unwrap();
+///     rate.sleep();
+/// }
+/// println!("{:?}", broadcaster.frame_statistics("link1"));
+/// ```
+pub struct ThresholdTfBroadcaster {
+    broadcaster: TfBroadcaster,
+    filter: Mutex<ChangeFilter>,
+}
+
+impl ThresholdTfBroadcaster {
+    /// Create a new ThresholdTfBroadcaster
+    pub fn new(threshold: BroadcastThreshold) -> Result<Self, TfError> {
+        Ok(Self::new_with_broadcaster(
+            TfBroadcaster::try_new()?,
+            threshold,
+        ))
+    }
+
+    /// Create a new ThresholdTfBroadcaster sending through the given broadcaster
+    pub fn new_with_broadcaster(broadcaster: TfBroadcaster, threshold: BroadcastThreshold) -> Self {
+        Self {
+            broadcaster,
+            filter: Mutex::new(ChangeFilter::new(threshold)),
+        }
+    }
+
+    /// Broadcast transform if it changed enough. Returns whether it was sent.
+    pub fn send_transform(&self, tf: TransformStamped) -> Result<bool, TfError> {
+        Ok(self.send_transforms(vec![tf])? == 1)
+    }
+
+    /// Broadcast the transforms which changed enough in a single message. Returns the number of
+    /// transforms sent.
+    pub fn send_transforms(&self, transforms: Vec<TransformStamped>) -> Result<usize, TfError> {
+        // The filter stays locked until the transforms are sent, so that they are only recorded as
+        // sent once they were published.
+        let mut filter = self.lock();
+        let now = rosrust::now();
+        let filtered = filter.filter(transforms, now);
+        let len = filtered.sent.len();
+        self.broadcaster.send_transforms(filtered.sent.clone())?;
+        filter.commit(filtered, now);
+        Ok(len)
+    }
+
+    /// Returns the statistics of all child frames
+    pub fn statistics(&self) -> HashMap<String, BroadcastStatistics> {
+        self.lock()
+            .frames
+            .iter()
+            .map(|(frame, state)| (frame.clone(), state.statistics))
+            .collect()
+    }
+
+    /// Returns the statistics of the given child frame
+    pub fn frame_statistics(&self, child_frame_id: &str) -> Option<BroadcastStatistics> {
+        self.lock()
+            .frames
+            .get(child_frame_id)
+            .map(|state| state.statistics)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ChangeFilter> {
+        self.filter.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[derive(Debug, Default)]
+struct FrameState {
+    last_sent: Option<SentTransform>,
+    statistics: BroadcastStatistics,
+}
+
+#[derive(Debug)]
+struct SentTransform {
+    transform: TransformStamped,
+    time: Time,
+}
+
+/// Result of [`ChangeFilter::filter`]
+#[derive(Debug, Default)]
+struct Filtered {
+    /// Transforms to send
+    sent: Vec<TransformStamped>,
+    /// Child frames of the suppressed transforms
+    suppressed: Vec<String>,
+}
+
+#[derive(Debug)]
+struct ChangeFilter {
+    threshold: BroadcastThreshold,
+    frames: HashMap<String, FrameState>,
+}
+
+impl ChangeFilter {
+    fn new(threshold: BroadcastThreshold) -> Self {
+        Self {
+            threshold,
+            frames: HashMap::new(),
+        }
+    }
+
+    /// Selects the transforms which changed enough since the last ones sent, which are sent at
+    /// `now`. Nothing is recorded until [`Self::commit`], so that a failed send changes nothing.
+    fn filter(&self, transforms: Vec<TransformStamped>, now: Time) -> Filtered {
+        let mut filtered = Filtered::default();
+        for tf in transforms {
+            // A transform selected earlier in the same batch is the last one sent for its frame.
+            let last = match filtered
+                .sent
+                .iter()
+                .rev()
+                .find(|sent| sent.child_frame_id == tf.child_frame_id)
+            {
+                Some(last) => Some((last, now)),
+                None => self
+                    .frames
+                    .get(&tf.child_frame_id)
+                    .and_then(|state| state.last_sent.as_ref())
+                    .map(|last| (&last.transform, last.time)),
+            };
+            let changed = match last {
+                Some((last, sent_at)) => {
+                    elapsed(sent_at, now) >= self.threshold.keep_alive
+                        || is_changed(last, &tf, &self.threshold)
+                }
+                None => true,
+            };
+            if changed {
+                filtered.sent.push(tf);
+            } else {
+                filtered.suppressed.push(tf.child_frame_id);
+            }
+        }
+        filtered
+    }
+
+    /// Records the transforms as sent at `now`, and counts the suppressed ones
+    fn commit(&mut self, filtered: Filtered, now: Time) {
+        for child_frame_id in filtered.suppressed {
+            let state = self.frames.entry(child_frame_id).or_default();
+            state.statistics.suppressed += 1;
+        }
+        for tf in filtered.sent {
+            let state = self.frames.entry(tf.child_frame_id.clone()).or_default();
+            state.statistics.sent += 1;
+            state.last_sent = Some(SentTransform {
+                transform: tf,
+                time: now,
+            });
+        }
+    }
+}
+
+fn is_changed(
+    last: &TransformStamped,
+    tf: &TransformStamped,
+    threshold: &BroadcastThreshold,
+) -> bool {
+    if last.header.frame_id != tf.header.frame_id {
+        return true;
+    }
+    let last = isometry_from_transform(&last.transform);
+    let current = isometry_from_transform(&tf.transform);
+    (current.translation.vector - last.translation.vector).norm() > threshold.translation
+        || current.rotation.angle_to(&last.rotation) > threshold.rotation
+}
+
+fn elapsed(from: Time, to: Time) -> Duration {
+    if to > from {
+        to - from
+    } else {
+        Duration::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::transform_with_yaw;
+
+    fn at(sec: u32) -> Time {
+        Time { sec, nsec: 0 }
+    }
+
+    /// Filters the transform and records it as sent at `sec`, like a successful send
+    fn send(filter: &mut ChangeFilter, tf: TransformStamped, sec: u32) -> bool {
+        let filtered = filter.filter(vec![tf], at(sec));
+        let is_sent = !filtered.sent.is_empty();
+        filter.commit(filtered, at(sec));
+        is_sent
+    }
+
+    #[test]
+    fn test_change_filter() {
+        let mut filter = ChangeFilter::new(BroadcastThreshold {
+            translation: 0.01,
+            rotation: 0.01,
+            keep_alive: Duration::from_seconds(5),
+        });
+        assert!(send(
+            &mut filter,
+            transform_with_yaw("base_link", "link1", 0, 0.0, 0.0),
+            0
+        ));
+        assert!(!send(
+            &mut filter,
+            transform_with_yaw("base_link", "link1", 1, 0.005, 0.0),
+            1
+        ));
+        assert!(!send(
+            &mut filter,
+            transform_with_yaw("base_link", "link1", 1, 0.0, 0.005),
+            1
+        ));
+        assert!(send(
+            &mut filter,
+            transform_with_yaw("base_link", "link1", 2, 0.02, 0.0),
+            2
+        ));
+        assert!(send(
+            &mut filter,
+            transform_with_yaw("base_link", "link1", 2, 0.02, 0.02),
+            2
+        ));
+        // Keep alive
+        assert!(!send(
+            &mut filter,
+            transform_with_yaw("base_link", "link1", 6, 0.02, 0.02),
+            6
+        ));
+        assert!(send(
+            &mut filter,
+            transform_with_yaw("base_link", "link1", 7, 0.02, 0.02),
+            7
+        ));
+        // Re-parenting is always sent
+        let mut tf = transform_with_yaw("base_link", "link1", 7, 0.02, 0.02);
+        tf.header.frame_id = "world".to_string();
+        assert!(send(&mut filter, tf, 7));
+
+        let statistics = filter.frames["link1"].statistics;
+        assert_eq!(statistics.sent, 5);
+        assert_eq!(statistics.suppressed, 3);
+        assert!((statistics.suppressed_ratio() - 3.0 / 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_change_filter_keep_alive_uses_send_time() {
+        let mut filter = ChangeFilter::new(BroadcastThreshold {
+            keep_alive: Duration::from_seconds(5),
+            ..BroadcastThreshold::default()
+        });
+        // A static transform keeps its stamp, but is still sent once the keep-alive has passed.
+        let tf = transform_with_yaw("base_link", "link1", 0, 0.0, 0.0);
+        assert!(send(&mut filter, tf.clone(), 10));
+        assert!(!send(&mut filter, tf.clone(), 14));
+        assert!(send(&mut filter, tf.clone(), 15));
+        // Newer stamps alone do not count as time passed since the last send.
+        let tf = transform_with_yaw("base_link", "link1", 100, 0.0, 0.0);
+        assert!(!send(&mut filter, tf, 16));
+    }
+
+    #[test]
+    fn test_change_filter_multiple_frames() {
+        let mut filter = ChangeFilter::new(BroadcastThreshold::default());
+        let tf1 = transform_with_yaw("base_link", "link1", 0, 0.0, 0.0);
+        let tf2 = transform_with_yaw("base_link", "link2", 0, 0.0, 0.0);
+        let filtered = filter.filter(vec![tf1.clone(), tf2.clone()], at(0));
+        assert_eq!(filtered.sent.len(), 2);
+        filter.commit(filtered, at(0));
+        let filtered = filter.filter(vec![tf1, tf2], at(0));
+        assert!(filtered.sent.is_empty());
+        filter.commit(filtered, at(0));
+        assert_eq!(filter.frames["link1"].statistics.suppressed, 1);
+        assert_eq!(filter.frames["link2"].statistics.suppressed, 1);
+    }
+
+    #[test]
+    fn test_change_filter_uncommitted() {
+        let mut filter = ChangeFilter::new(BroadcastThreshold::default());
+        let tf = transform_with_yaw("base_link", "link1", 0, 0.0, 0.0);
+        // A transform which failed to be sent is not recorded, so it is sent again.
+        assert_eq!(filter.filter(vec![tf.clone()], at(0)).sent.len(), 1);
+        assert_eq!(filter.filter(vec![tf.clone()], at(0)).sent.len(), 1);
+        assert!(!filter.frames.contains_key("link1"));
+        // Unchanged transforms of the same batch are suppressed, but only counted once sent.
+        let filtered = filter.filter(vec![tf.clone(), tf], at(0));
+        assert_eq!(filtered.sent.len(), 1);
+        assert_eq!(filtered.suppressed.len(), 1);
+        assert!(!filter.frames.contains_key("link1"));
+        filter.commit(filtered, at(0));
+        assert_eq!(
+            filter.frames["link1"].statistics,
+            BroadcastStatistics {
+                sent: 1,
+                suppressed: 1,
+            }
+        );
+    }
+}
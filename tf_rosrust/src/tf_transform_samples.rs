@@ -0,0 +1,264 @@
+use std::{mem, sync::Arc};
+
+use crate::transforms::geometry_msgs::TransformStamped;
+
+/// Maximum number of samples of a chunk
+const CHUNK_SIZE: usize = 64;
+
+fn stamp_nanos(sample: &TransformStamped) -> i64 {
+    sample.header.stamp.nanos()
+}
+
+/// The samples of a transform chain, sorted by stamp
+///
+/// The samples are stored in chunks which are shared between the clones of the chain, so that
+/// modifying a clone only copies the chunk it modifies instead of the whole history. New samples
+/// are usually appended to the last chunk, and expired ones removed from the first.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TransformSamples {
+    // Never empty, and sorted by stamp across the chunks
+    chunks: Vec<Arc<Vec<TransformStamped>>>,
+    len: usize,
+}
+
+/// Result of [`TransformSamples::search`]
+pub(crate) enum SearchResult<'a> {
+    /// A sample has the searched stamp.
+    Exact(&'a TransformStamped),
+    /// No sample has the searched stamp. Contains the samples right before and after it.
+    Between(Option<&'a TransformStamped>, Option<&'a TransformStamped>),
+}
+
+impl TransformSamples {
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn first(&self) -> Option<&TransformStamped> {
+        self.chunks.first().and_then(|chunk| chunk.first())
+    }
+
+    pub(crate) fn last(&self) -> Option<&TransformStamped> {
+        self.chunks.last().and_then(|chunk| chunk.last())
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &TransformStamped> {
+        self.chunks.iter().flat_map(|chunk| chunk.iter())
+    }
+
+    /// Returns an estimate of the memory used by the samples in bytes, including the chunks
+    /// shared with other clones
+    pub(crate) fn memory_usage(&self) -> usize {
+        let chunks: usize = self
+            .chunks
+            .iter()
+            .map(|chunk| chunk.capacity() * mem::size_of::<TransformStamped>())
+            .sum();
+        let frame_ids: usize = self
+            .iter()
+            .map(|sample| sample.header.frame_id.capacity() + sample.child_frame_id.capacity())
+            .sum();
+        self.chunks.capacity() * mem::size_of::<Arc<Vec<TransformStamped>>>() + chunks + frame_ids
+    }
+
+    /// Returns the sample with the stamp, or the samples around it
+    pub(crate) fn search(&self, nanos: i64) -> SearchResult<'_> {
+        let index = self.chunk_index(nanos);
+        let Some(chunk) = self.chunks.get(index) else {
+            return SearchResult::Between(self.last(), None);
+        };
+        match chunk.binary_search_by(|sample| stamp_nanos(sample).cmp(&nanos)) {
+            Ok(i) => SearchResult::Exact(&chunk[i]),
+            Err(i) => {
+                let before = match i.checked_sub(1) {
+                    Some(i) => chunk.get(i),
+                    None => index
+                        .checked_sub(1)
+                        .and_then(|index| self.chunks[index].last()),
+                };
+                SearchResult::Between(before, chunk.get(i))
+            }
+        }
+    }
+
+    pub(crate) fn contains(&self, nanos: i64) -> bool {
+        matches!(self.search(nanos), SearchResult::Exact(_))
+    }
+
+    /// Inserts the sample, or replaces the one with the same stamp
+    pub(crate) fn insert(&mut self, msg: TransformStamped) {
+        let nanos = stamp_nanos(&msg);
+        if self.chunks.is_empty() {
+            self.chunks.push(Arc::new(vec![msg]));
+            self.len = 1;
+            return;
+        }
+        let index = self.chunk_index(nanos).min(self.chunks.len() - 1);
+        let chunk = &mut self.chunks[index];
+        match chunk.binary_search_by(|sample| stamp_nanos(sample).cmp(&nanos)) {
+            Ok(i) => Arc::make_mut(chunk)[i] = msg,
+            // Only the last chunk can be appended to. A new chunk is started once it is full,
+            // so that the full chunks stay shared.
+            Err(i) if i == chunk.len() && chunk.len() >= CHUNK_SIZE => {
+                self.chunks.push(Arc::new(vec![msg]));
+                self.len += 1;
+            }
+            Err(i) => {
+                let chunk = Arc::make_mut(chunk);
+                chunk.insert(i, msg);
+                if chunk.len() > CHUNK_SIZE {
+                    let second_half = chunk.split_off(chunk.len() / 2);
+                    self.chunks.insert(index + 1, Arc::new(second_half));
+                }
+                self.len += 1;
+            }
+        }
+    }
+
+    /// Removes the samples older than the stamp
+    pub(crate) fn remove_before(&mut self, nanos: i64) {
+        let index = self.chunk_index(nanos);
+        let in_chunk = self.chunks.get(index).map_or(0, |chunk| {
+            chunk.partition_point(|sample| stamp_nanos(sample) < nanos)
+        });
+        let count = self.chunks[..index]
+            .iter()
+            .map(|chunk| chunk.len())
+            .sum::<usize>()
+            + in_chunk;
+        self.remove_oldest(count);
+    }
+
+    /// Removes the given number of samples, starting with the oldest
+    pub(crate) fn remove_oldest(&mut self, count: usize) {
+        let count = count.min(self.len);
+        let mut remaining = count;
+        let mut expired_chunks = 0;
+        for chunk in &self.chunks {
+            if chunk.len() > remaining {
+                break;
+            }
+            remaining -= chunk.len();
+            expired_chunks += 1;
+        }
+        self.chunks.drain(..expired_chunks);
+        if remaining > 0 {
+            if let Some(chunk) = self.chunks.first_mut() {
+                Arc::make_mut(chunk).drain(..remaining);
+            }
+        }
+        self.len -= count;
+    }
+
+    /// Returns the index of the first chunk whose newest sample is not older than the stamp
+    fn chunk_index(&self, nanos: i64) -> usize {
+        self.chunks
+            .partition_point(|chunk| chunk.last().is_some_and(|last| stamp_nanos(last) < nanos))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::transform;
+
+    fn samples(secs: impl IntoIterator<Item = u32>) -> TransformSamples {
+        let mut samples = TransformSamples::default();
+        for sec in secs {
+            samples.insert(transform("odom", "base_link", sec, sec.into()));
+        }
+        samples
+    }
+
+    fn secs(samples: &TransformSamples) -> Vec<u32> {
+        samples
+            .iter()
+            .map(|sample| sample.header.stamp.sec)
+            .collect()
+    }
+
+    fn nanos(sec: u32) -> i64 {
+        i64::from(sec) * 1_000_000_000
+    }
+
+    #[test]
+    fn test_insert() {
+        // Out of order and repeated across several chunks
+        let even = (0..150).map(|sec| sec * 2);
+        let mut samples = samples(even.clone().rev().chain(even));
+        assert_eq!(samples.len(), 150);
+        for sec in (1..300).step_by(2) {
+            samples.insert(transform("odom", "base_link", sec, 0.0));
+        }
+        assert_eq!(samples.len(), 300);
+        assert_eq!(secs(&samples), (0..300).collect::<Vec<_>>());
+        assert!(samples.chunks.iter().all(|chunk| chunk.len() <= CHUNK_SIZE));
+
+        // A sample with the same stamp is replaced
+        samples.insert(transform("odom", "base_link", 100, -1.0));
+        assert_eq!(samples.len(), 300);
+        assert!(matches!(
+            samples.search(nanos(100)),
+            SearchResult::Exact(sample) if sample.transform.translation.x == -1.0
+        ));
+    }
+
+    #[test]
+    fn test_search() {
+        let samples = samples((0..200).map(|sec| sec * 2));
+        assert!(samples.contains(nanos(128)));
+        assert!(!samples.contains(nanos(129)));
+        for sec in [1, 127, 129, 397] {
+            assert!(matches!(
+                samples.search(nanos(sec)),
+                SearchResult::Between(Some(before), Some(after))
+                    if before.header.stamp.sec == sec - 1 && after.header.stamp.sec == sec + 1
+            ));
+        }
+        assert!(matches!(
+            samples.search(-1),
+            SearchResult::Between(None, Some(after)) if after.header.stamp.sec == 0
+        ));
+        assert!(matches!(
+            samples.search(nanos(399)),
+            SearchResult::Between(Some(before), None) if before.header.stamp.sec == 398
+        ));
+        assert!(matches!(
+            TransformSamples::default().search(0),
+            SearchResult::Between(None, None)
+        ));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut samples = samples(0..200);
+        samples.remove_before(nanos(70));
+        assert_eq!(samples.len(), 130);
+        assert_eq!(samples.first().unwrap().header.stamp.sec, 70);
+        samples.remove_before(nanos(70));
+        assert_eq!(samples.len(), 130);
+        samples.remove_oldest(100);
+        assert_eq!(secs(&samples), (170..200).collect::<Vec<_>>());
+        samples.remove_oldest(100);
+        assert_eq!(samples.len(), 0);
+        assert!(samples.chunks.is_empty());
+    }
+
+    #[test]
+    fn test_shared_chunks() {
+        let samples = samples(0..200);
+        let mut clone = samples.clone();
+        clone.insert(transform("odom", "base_link", 200, 0.0));
+        clone.remove_oldest(1);
+        // Only the first and the last chunks are copied.
+        let shared = samples
+            .chunks
+            .iter()
+            .zip(&clone.chunks)
+            .filter(|(a, b)| Arc::ptr_eq(a, b))
+            .count();
+        assert_eq!(shared, samples.chunks.len() - 2);
+        assert_eq!(secs(&samples), (0..200).collect::<Vec<_>>());
+        assert_eq!(secs(&clone), (1..201).collect::<Vec<_>>());
+    }
+}
@@ -8,7 +8,15 @@ rosrust::rosmsg_include!(
     geometry_msgs / Quaternion,
     geometry_msgs / TransformStamped,
     std_msgs / Header,
-    tf2_msgs / TFMessage
+    tf2_msgs / TFMessage,
+    tf2_msgs / FrameGraph,
+    tf2_msgs / TF2Error,
+    tf2_msgs / LookupTransformActionGoal,
+    tf2_msgs / LookupTransformActionFeedback,
+    tf2_msgs / LookupTransformActionResult,
+    actionlib_msgs / GoalID,
+    actionlib_msgs / GoalStatus,
+    actionlib_msgs / GoalStatusArray
 );
 
 use geometry_msgs::{Pose, Quaternion, Transform, TransformStamped, Vector3};
@@ -0,0 +1,9 @@
+use std::fmt::Display;
+
+/// Logs the warning with the `tf_rosrust` prefix, unless rosrust is not initialized, in which case
+/// the logging macros would panic, e.g. for a buffer used without a node
+pub(crate) fn warn(message: impl Display) {
+    if rosrust::is_initialized() {
+        rosrust::ros_warn!("tf_rosrust: {message}");
+    }
+}